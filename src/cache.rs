@@ -0,0 +1,180 @@
+use crate::sorter::Step;
+
+/// Bytes per element of the simulated array.
+const ELEMENT: usize = size_of::<u32>();
+
+/// One level of a simulated cache.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    /// Capacity in bytes.
+    pub size: usize,
+    /// Lines per set.
+    pub ways: usize,
+    /// Cycles taken by a hit in this level.
+    pub latency: u64,
+}
+
+/// A cache hierarchy, smallest and fastest level first.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Line size in bytes, shared by all levels.
+    pub line: usize,
+    pub levels: Vec<Level>,
+    /// Cycles taken by an access that misses every level.
+    pub memory: u64,
+}
+
+impl Config {
+    /// Default latency of the `k`th level, for levels added without one.
+    pub const fn latency(k: usize) -> u64 {
+        match k {
+            0 => 4,
+            1 => 12,
+            2 => 40,
+            _ => 80,
+        }
+    }
+}
+
+impl Default for Config {
+    /// A typical desktop CPU core.
+    fn default() -> Self {
+        Self {
+            line: 64,
+            levels: vec![
+                Level {
+                    size: 32 << 10,
+                    ways: 8,
+                    latency: Self::latency(0),
+                },
+                Level {
+                    size: 256 << 10,
+                    ways: 8,
+                    latency: Self::latency(1),
+                },
+                Level {
+                    size: 8 << 20,
+                    ways: 16,
+                    latency: Self::latency(2),
+                },
+            ],
+            memory: 200,
+        }
+    }
+}
+
+/// Hits and misses of one level.
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Stats {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn hit_rate(self) -> f64 {
+        let accesses = self.hits + self.misses;
+        if accesses == 0 {
+            0.0
+        } else {
+            self.hits as f64 / accesses as f64
+        }
+    }
+}
+
+/// What a run did to the cache.
+#[derive(Clone, Default)]
+pub struct Report {
+    /// Per level, in the order of `Config::levels`.
+    pub levels: Vec<Stats>,
+    /// Estimated cost of all accesses.
+    pub cycles: u64,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (k, level) in self.levels.iter().enumerate() {
+            writeln!(
+                f,
+                "L{}: {} hits, {} misses ({:.1}% hits)",
+                k + 1,
+                level.hits,
+                level.misses,
+                level.hit_rate() * 100.0
+            )?;
+        }
+        write!(f, "≈ {} cycles", self.cycles)
+    }
+}
+
+/// An inclusive, set-associative cache hierarchy with LRU replacement, fed with
+/// the elements touched by every operation of a run.
+///
+/// All cursors of a parallel method share it, like threads sharing a core.
+#[derive(Clone)]
+pub struct Cache {
+    config: Config,
+    /// Per level and set, the lines held, most recently used first.
+    sets: Vec<Vec<Vec<usize>>>,
+    pub report: Report,
+}
+
+impl Cache {
+    pub fn new(config: Config) -> Self {
+        let sets = config
+            .levels
+            .iter()
+            .map(|level| {
+                let count = (level.size / (config.line * level.ways).max(1)).max(1);
+                vec![Vec::with_capacity(level.ways); count]
+            })
+            .collect();
+        Self {
+            report: Report {
+                levels: vec![Stats::default(); config.levels.len()],
+                cycles: 0,
+            },
+            config,
+            sets,
+        }
+    }
+
+    /// Accesses every element `step` touches.
+    pub fn step(&mut self, step: &Step) {
+        match *step {
+            Step::Read(i) | Step::Write(i) => self.access(i),
+            Step::Compare(i, j) | Step::Swap(i, j) => {
+                self.access(i);
+                self.access(j);
+            }
+        }
+    }
+
+    fn access(&mut self, index: usize) {
+        let line = index * ELEMENT / self.config.line.max(1);
+        let mut latency = self.config.memory;
+        let mut hit = self.config.levels.len();
+        for (k, level) in self.config.levels.iter().enumerate() {
+            let sets = &mut self.sets[k];
+            let count = sets.len();
+            let set = &mut sets[line % count];
+            if let Some(way) = set.iter().position(|&held| held == line) {
+                set[..=way].rotate_right(1);
+                self.report.levels[k].hits += 1;
+                latency = level.latency;
+                hit = k;
+                break;
+            }
+            self.report.levels[k].misses += 1;
+        }
+        // Bring the line into every level that missed.
+        for (k, level) in self.config.levels.iter().enumerate().take(hit) {
+            let sets = &mut self.sets[k];
+            let count = sets.len();
+            let set = &mut sets[line % count];
+            set.insert(0, line);
+            set.truncate(level.ways);
+        }
+        self.report.cycles += latency;
+    }
+}
@@ -0,0 +1,632 @@
+use crate::cache::{self, Level};
+use crate::comparator::Comparator;
+use crate::cost::Costs;
+use crate::dataset::{Dataset, Scale};
+use crate::export;
+use crate::external;
+#[cfg(feature = "fallible")]
+use crate::fault::Fault;
+use crate::grade;
+use crate::methods::{METHODS, MODIFIERS};
+use crate::script;
+use crate::sorter::{Limits, Method};
+use crate::trace::Sink;
+use crate::wasm;
+use crate::View;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: sortvis [OPTIONS]
+       sortvis info [METHOD]
+       sortvis torture [--size N] [--timeout SECS]
+       sortvis grade [METHOD] [GRADE OPTIONS]
+       sortvis states METHOD [STATES OPTIONS]
+
+Options:
+  -m, --method NAME        Sorting method to select
+      --script FILE        Select the method written in the script FILE
+      --wasm FILE          Select the sort() export of the WebAssembly module FILE
+      --fuel N             Stop WebAssembly methods after N units of fuel
+  -n, --size N             Number of elements
+      --data FILE          Sort the values in FILE: a CSV or TSV column, a JSON
+                           array, whitespace separated numbers or lines of text
+      --column COLUMN      Column of a CSV or TSV file, by header or from 1
+      --scale SCALE        linear (default) or rank: how values become bar
+                           heights
+  -i, --input NAME         Modifier applied to the generated data (e.g. shuffle)
+      --seed N             Seed for shuffling and randomized methods
+      --delay US           Sleep US microseconds per unit of cost before every
+                           operation
+      --ops-per-frame K    Perform K operations per frame instead of sleeping
+      --comparator KIND:N  Answer comparisons with a consistent, random,
+                           non-transitive, lies-after:N or keyed:N comparator,
+                           the last giving N consecutive values the same key
+      --budget N           Abort the sort after N operations
+      --timeout SECS       Abort the sort after SECS seconds
+      --cache LEVELS       Simulate a cache: default, or SIZE:WAYS for each level
+                           from L1 down, e.g. 32K:8,256K:8,8M:16
+      --cache-line BYTES   Line size of the simulated cache (default 64)
+      --memory PAGES       Simulate a disk with PAGES pages of memory (default 4)
+      --page-size N        Elements per page of the simulated disk (default 4)
+      --costs MODEL        Operation costs for virtual time: uniform (default),
+                           flash, strings, disk or READ:COMPARE:SWAP:WRITE:PAGE
+      --view VIEW          full (default), bars or network
+  -a, --autostart          Start sorting right away
+  -x, --exit               Exit once the sort has finished
+      --serve PORT         Accept remote control on localhost:PORT over HTTP and
+                           WebSocket
+      --trace DEST         Write every operation as a line of JSON to DEST: - for
+                           stdout or the path of a listening Unix socket
+      --fault KIND:N       Inject a fault: wrong-read:N, noop-swap:N or panic:N
+                           (requires the `fallible` feature)
+  -l, --list               List all methods and modifiers
+  -h, --help               Print this help
+
+Grade options:
+      --script FILE        Grade the script FILE instead of a built-in method
+      --wasm FILE          Grade the WebAssembly module FILE
+      --fuel N             Fuel for each run of the module
+      --reference NAME     Method bounding the operation counts (default heap)
+      --factor F           Allow F times the operations of the reference
+                           (default 2)
+      --sizes N,N,...      Sizes of the inputs (default 1,2,10,100,1000)
+      --stable             Check stability, as for built-in methods claiming it
+      --seed N             Seed for the inputs (default 0)
+      --timeout SECS       Fail runs taking longer than SECS (default 10)
+      --format FORMAT      Report as json (default) or junit
+  -o, --output FILE        Write the report to FILE instead of stdout
+
+States options:
+  -n, --size N             Number of elements (default 50)
+      --data FILE          Sort the values in FILE instead
+      --column COLUMN      Column of a CSV or TSV file, by header or from 1
+      --scale SCALE        linear (default) or rank
+  -i, --input NAME         Modifier applied to the data (e.g. shuffle)
+      --seed N             Seed for shuffling and randomized methods
+      --every K            Write the array every K operations (default 1)
+      --budget N           Abort the sort after N operations
+      --format FORMAT      Write csv (default) or json lines
+  -o, --output FILE        Write the arrays to FILE instead of stdout
+";
+
+/// What the command line asks for.
+pub enum Command {
+    Run(Box<Options>),
+    List,
+    Info(Option<String>),
+    /// Check every method against inconsistent comparators.
+    Torture {
+        size: u32,
+        timeout: Duration,
+    },
+    /// Grade a method against a suite of inputs.
+    Grade(grade::Config),
+    /// Write every array a method goes through, with the seed for the input.
+    States(export::Config, Option<u64>),
+    Help,
+}
+
+/// Initial configuration of the visualizer.
+pub struct Options {
+    /// Index into `METHODS`.
+    pub method: usize,
+    /// Method loaded from a file, selected instead of `method`.
+    pub loaded: Option<&'static Method>,
+    /// Source of the script, if one was loaded.
+    pub script: Option<String>,
+    pub fuel: u64,
+    pub size: u32,
+    /// Values loaded from a file, used instead of generating `size` elements.
+    pub dataset: Option<Dataset>,
+    pub scale: Scale,
+    pub input: Option<&'static Method>,
+    pub seed: Option<u64>,
+    pub delay: Option<u64>,
+    pub ops_per_frame: Option<f64>,
+    pub comparator: Comparator,
+    pub lie_after: u64,
+    pub limits: Limits,
+    pub cache: Option<cache::Config>,
+    pub external: Option<external::Config>,
+    pub costs: Costs,
+    pub view: View,
+    pub autostart: bool,
+    pub exit: bool,
+    /// Port of the remote-control server, if enabled.
+    pub serve: Option<u16>,
+    /// Where to write the operations of tracked runs, if anywhere.
+    pub trace: Option<Sink>,
+    #[cfg(feature = "fallible")]
+    pub fault: Option<(Fault, u64)>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            method: 0,
+            loaded: None,
+            script: None,
+            fuel: wasm::DEFAULT_FUEL,
+            size: 50,
+            dataset: None,
+            scale: Scale::Linear,
+            input: None,
+            seed: None,
+            delay: None,
+            ops_per_frame: None,
+            comparator: Comparator::Consistent,
+            lie_after: 0,
+            limits: Limits::default(),
+            cache: None,
+            external: None,
+            costs: Costs::default(),
+            view: View::Full,
+            autostart: false,
+            exit: false,
+            serve: None,
+            trace: None,
+            #[cfg(feature = "fallible")]
+            fault: None,
+        }
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut module = None;
+    let mut data = None;
+    let mut column = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "info" => return Ok(Command::Info(args.next())),
+            "torture" => return parse_torture(args),
+            "grade" => return parse_grade(args),
+            "states" => return parse_states(args),
+            "-l" | "--list" => return Ok(Command::List),
+            "-h" | "--help" => return Ok(Command::Help),
+            "-m" | "--method" => {
+                let name = value()?;
+                options.method = METHODS
+                    .iter()
+                    .position(|m| m.name == name)
+                    .ok_or_else(|| format!("unknown method: {name}"))?;
+            }
+            "--script" => {
+                let path = value()?;
+                let source = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("cannot read {path}: {err}"))?;
+                script::load(&source).map_err(|err| format!("{path}: {err}"))?;
+                options.script = Some(source);
+                options.loaded = Some(&script::METHOD);
+            }
+            "--wasm" => {
+                let path = value()?;
+                let bytes =
+                    std::fs::read(&path).map_err(|err| format!("cannot read {path}: {err}"))?;
+                module = Some((path, bytes));
+                options.loaded = Some(&wasm::METHOD);
+            }
+            "--fuel" => options.fuel = number(&arg, &value()?)?,
+            "-n" | "--size" => options.size = number(&arg, &value()?)?,
+            "--data" => data = Some(value()?),
+            "--column" => column = Some(value()?),
+            "--scale" => options.scale = scale(&value()?)?,
+            "-i" | "--input" => options.input = input(&value()?)?,
+            "--seed" => options.seed = Some(number(&arg, &value()?)?),
+            "--delay" => options.delay = Some(number(&arg, &value()?)?),
+            "--ops-per-frame" => options.ops_per_frame = Some(positive(&arg, &value()?)?),
+            "--comparator" => {
+                let spec = value()?;
+                let (kind, n) = spec.split_once(':').unwrap_or((&spec, "0"));
+                options.comparator = Comparator::ALL
+                    .into_iter()
+                    .find(|c| c.name() == kind)
+                    .ok_or_else(|| format!("unknown comparator: {kind}"))?;
+                options.lie_after = number(&arg, n)?;
+            }
+            "--budget" => options.limits.budget = Some(number(&arg, &value()?)?),
+            "--timeout" => options.limits.timeout = Some(seconds(&arg, &value()?)?),
+            "--cache" => {
+                let spec = value()?;
+                let line = options.cache.as_ref().map(|cache| cache.line);
+                let mut cache = cache_config(&arg, &spec)?;
+                cache.line = line.unwrap_or(cache.line);
+                options.cache = Some(cache);
+            }
+            "--cache-line" => {
+                let line = number(&arg, &value()?)?;
+                if line == 0 {
+                    return Err(String::from("--cache-line must be positive"));
+                }
+                options
+                    .cache
+                    .get_or_insert_with(cache::Config::default)
+                    .line = line;
+            }
+            "--memory" => {
+                let frames = number(&arg, &value()?)?;
+                if frames < external::Config::MIN_FRAMES {
+                    return Err(format!(
+                        "--memory must be at least {}",
+                        external::Config::MIN_FRAMES
+                    ));
+                }
+                options
+                    .external
+                    .get_or_insert_with(external::Config::default)
+                    .frames = frames;
+            }
+            "--page-size" => {
+                let page = number(&arg, &value()?)?;
+                if page == 0 {
+                    return Err(String::from("--page-size must be positive"));
+                }
+                options
+                    .external
+                    .get_or_insert_with(external::Config::default)
+                    .page = page;
+            }
+            "--costs" => options.costs = costs(&arg, &value()?)?,
+            "--view" => {
+                let name = value()?;
+                options.view = match name.as_str() {
+                    "full" => View::Full,
+                    "bars" => View::Bars,
+                    "network" => View::Network,
+                    _ => return Err(format!("unknown view: {name}")),
+                };
+            }
+            "-a" | "--autostart" => options.autostart = true,
+            "-x" | "--exit" => options.exit = true,
+            "--serve" => options.serve = Some(number(&arg, &value()?)?),
+            "--trace" => options.trace = Some(Sink::parse(&value()?)),
+            #[cfg(feature = "fallible")]
+            "--fault" => {
+                let spec = value()?;
+                let (kind, n) = spec.split_once(':').unwrap_or((&spec, "1"));
+                let fault = Fault::ALL
+                    .into_iter()
+                    .find(|f| f.name() == kind)
+                    .ok_or_else(|| format!("unknown fault: {kind}"))?;
+                options.fault = Some((fault, number(&arg, n)?));
+            }
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    if options.size == 0 {
+        return Err(String::from("--size must be positive"));
+    }
+    // Loaded once every option is known, as the fuel may come after the file.
+    if let Some((path, bytes)) = module {
+        wasm::load(&path, &bytes, options.fuel).map_err(|err| format!("{path}: {err}"))?;
+    }
+    if let Some(path) = data {
+        options.dataset = Some(Dataset::load(&path, column.as_deref())?);
+    } else if column.is_some() {
+        return Err(String::from("--column needs --data"));
+    }
+    Ok(Command::Run(Box::new(options)))
+}
+
+fn parse_torture(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut size = 100;
+    let mut timeout = Duration::from_secs(2);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {arg}"))?;
+        match arg.as_str() {
+            "-n" | "--size" => size = number(&arg, &value)?,
+            "--timeout" => timeout = seconds(&arg, &value)?,
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+    Ok(Command::Torture { size, timeout })
+}
+
+/// Parses the arguments of `sortvis grade`, those after the subcommand.
+fn parse_grade(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut method = None;
+    let mut module = None;
+    let mut fuel = wasm::DEFAULT_FUEL;
+    let mut stable = false;
+    let mut config = grade::Config {
+        method: &METHODS[0],
+        reference: find("heap")?,
+        factor: 2.0,
+        sizes: vec![1, 2, 10, 100, 1000],
+        stable: false,
+        seed: 0,
+        timeout: Duration::from_secs(10),
+        format: grade::Format::Json,
+        output: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--script" => {
+                let path = value()?;
+                let source = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("cannot read {path}: {err}"))?;
+                script::load(&source).map_err(|err| format!("{path}: {err}"))?;
+                method = Some(&script::METHOD);
+            }
+            "--wasm" => {
+                let path = value()?;
+                let bytes =
+                    std::fs::read(&path).map_err(|err| format!("cannot read {path}: {err}"))?;
+                module = Some((path, bytes));
+                method = Some(&wasm::METHOD);
+            }
+            "--fuel" => fuel = number(&arg, &value()?)?,
+            "--reference" => config.reference = find(&value()?)?,
+            "--factor" => config.factor = positive(&arg, &value()?)?,
+            "--sizes" => {
+                config.sizes = value()?
+                    .split(',')
+                    .map(|size| number(&arg, size))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--stable" => stable = true,
+            "--seed" => config.seed = number(&arg, &value()?)?,
+            "--timeout" => config.timeout = seconds(&arg, &value()?)?,
+            "--format" => {
+                let name = value()?;
+                config.format = match name.as_str() {
+                    "json" => grade::Format::Json,
+                    "junit" => grade::Format::Junit,
+                    _ => return Err(format!("unknown format: {name}")),
+                };
+            }
+            "-o" | "--output" => config.output = Some(value()?),
+            name if !name.starts_with('-') && method.is_none() => method = Some(find(name)?),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    if let Some((path, bytes)) = module {
+        wasm::load(&path, &bytes, fuel).map_err(|err| format!("{path}: {err}"))?;
+    }
+    config.method = method.ok_or("grade needs a METHOD, --script or --wasm")?;
+    config.stable = stable || config.method.info.stable;
+    if config.sizes.is_empty() {
+        return Err(String::from("--sizes needs at least one size"));
+    }
+    if config.sizes.contains(&0) {
+        return Err(String::from("--sizes must be positive"));
+    }
+    Ok(Command::Grade(config))
+}
+
+fn parse_states(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut method = None;
+    let mut data = None;
+    let mut column = None;
+    let mut seed = None;
+    let mut config = export::Config {
+        method: &METHODS[0],
+        size: 50,
+        dataset: None,
+        scale: Scale::Linear,
+        input: None,
+        every: 1,
+        budget: None,
+        format: export::Format::Csv,
+        output: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "-n" | "--size" => config.size = number(&arg, &value()?)?,
+            "--data" => data = Some(value()?),
+            "--column" => column = Some(value()?),
+            "--scale" => config.scale = scale(&value()?)?,
+            "-i" | "--input" => config.input = input(&value()?)?,
+            "--seed" => seed = Some(number(&arg, &value()?)?),
+            "--every" => config.every = number(&arg, &value()?)?,
+            "--budget" => config.budget = Some(number(&arg, &value()?)?),
+            "--format" => {
+                let name = value()?;
+                config.format = match name.as_str() {
+                    "csv" => export::Format::Csv,
+                    "json" => export::Format::Json,
+                    _ => return Err(format!("unknown format: {name}")),
+                };
+            }
+            "-o" | "--output" => config.output = Some(value()?),
+            name if !name.starts_with('-') && method.is_none() => method = Some(find(name)?),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    config.method = method.ok_or("states needs a METHOD")?;
+    if config.size == 0 {
+        return Err(String::from("--size must be positive"));
+    }
+    if config.every == 0 {
+        return Err(String::from("--every must be positive"));
+    }
+    if let Some(path) = data {
+        config.dataset = Some(Dataset::load(&path, column.as_deref())?);
+    } else if column.is_some() {
+        return Err(String::from("--column needs --data"));
+    }
+    Ok(Command::States(config, seed))
+}
+
+/// The modifier called `name`, or none for `sorted`.
+fn input(name: &str) -> Result<Option<&'static Method>, String> {
+    if name == "sorted" {
+        return Ok(None);
+    }
+    MODIFIERS
+        .iter()
+        .find(|m| m.name == name)
+        .map(Some)
+        .ok_or_else(|| format!("unknown input: {name}"))
+}
+
+fn scale(name: &str) -> Result<Scale, String> {
+    Scale::ALL
+        .into_iter()
+        .find(|s| s.name() == name)
+        .ok_or_else(|| format!("unknown scale: {name}"))
+}
+
+/// The method in `METHODS` called `name`.
+fn find(name: &str) -> Result<&'static Method, String> {
+    METHODS
+        .iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("unknown method: {name}"))
+}
+
+/// Parses `default` or a comma separated list of `SIZE:WAYS` levels.
+fn cache_config(arg: &str, spec: &str) -> Result<cache::Config, String> {
+    let mut config = cache::Config::default();
+    if spec == "default" {
+        return Ok(config);
+    }
+    config.levels = spec
+        .split(',')
+        .enumerate()
+        .map(|(k, level)| {
+            let (size, ways) = level
+                .split_once(':')
+                .ok_or_else(|| format!("invalid value for {arg}: {level}"))?;
+            let level = Level {
+                size: bytes(arg, size)?,
+                ways: number(arg, ways)?,
+                latency: cache::Config::latency(k),
+            };
+            if level.size == 0 || level.ways == 0 {
+                return Err(format!("invalid value for {arg}: {spec}"));
+            }
+            Ok(level)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(config)
+}
+
+/// Parses the name of a preset or `READ:COMPARE:SWAP:WRITE:PAGE`.
+fn costs(arg: &str, spec: &str) -> Result<Costs, String> {
+    if let Some((_, costs)) = Costs::PRESETS.iter().find(|(name, _)| *name == spec) {
+        return Ok(*costs);
+    }
+    let values = spec
+        .split(':')
+        .map(|value| number(arg, value))
+        .collect::<Result<Vec<u64>, _>>()?;
+    let [read, compare, swap, write, page] = values[..] else {
+        return Err(format!("invalid value for {arg}: {spec}"));
+    };
+    Ok(Costs {
+        read,
+        compare,
+        swap,
+        write,
+        page,
+    })
+}
+
+/// Parses a number of bytes with an optional `K` or `M` suffix.
+fn bytes(arg: &str, value: &str) -> Result<usize, String> {
+    let shift = match value.chars().last() {
+        Some('K' | 'k') => 10,
+        Some('M' | 'm') => 20,
+        _ => 0,
+    };
+    let digits = if shift == 0 {
+        value
+    } else {
+        &value[..value.len() - 1]
+    };
+    number::<usize>(arg, digits)?
+        .checked_shl(shift)
+        .ok_or_else(|| format!("invalid value for {arg}: {value}"))
+}
+
+fn seconds(arg: &str, value: &str) -> Result<Duration, String> {
+    Duration::try_from_secs_f64(number(arg, value)?)
+        .map_err(|e| format!("invalid value for {arg}: {e}"))
+}
+
+/// Parses a finite number greater than zero.
+fn positive(arg: &str, value: &str) -> Result<f64, String> {
+    number(arg, value)
+        .ok()
+        .filter(|v: &f64| v.is_finite() && *v > 0.0)
+        .ok_or_else(|| format!("invalid value for {arg}: {value}"))
+}
+
+fn number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {arg}: {value}"))
+}
+
+pub fn print_list() {
+    println!("methods:");
+    for m in METHODS {
+        println!("  {}", m.name);
+    }
+    println!("modifiers:");
+    for m in MODIFIERS {
+        println!("  {}", m.name);
+    }
+}
+
+/// Prints a summary of every method, or the full metadata of the one named `name`.
+pub fn print_info(name: Option<&str>) -> Result<(), String> {
+    let Some(name) = name else {
+        println!(
+            "{:<14} {:<11} {:<11} {:<11} {:<9} {:<7} {:<9} adaptive",
+            "name", "best", "average", "worst", "memory", "stable", "in-place"
+        );
+        for m in METHODS.iter().chain(MODIFIERS) {
+            println!(
+                "{:<14} {:<11} {:<11} {:<11} {:<9} {:<7} {:<9} {}",
+                m.name,
+                m.info.best,
+                m.info.average,
+                m.info.worst,
+                m.info.memory,
+                m.info.stable,
+                m.info.in_place,
+                m.info.adaptive
+            );
+        }
+        return Ok(());
+    };
+
+    let m = METHODS
+        .iter()
+        .chain(MODIFIERS)
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("unknown method: {name}"))?;
+    println!("{}: {}", m.name, m.info.description);
+    println!("  best:     {}", m.info.best);
+    println!("  average:  {}", m.info.average);
+    println!("  worst:    {}", m.info.worst);
+    println!("  memory:   {}", m.info.memory);
+    println!("  stable:   {}", m.info.stable);
+    println!("  in-place: {}", m.info.in_place);
+    println!("  adaptive: {}", m.info.adaptive);
+    for reference in m.info.references {
+        println!("  see {reference}");
+    }
+    Ok(())
+}
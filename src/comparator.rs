@@ -0,0 +1,106 @@
+use crate::methods::rng;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Mutex;
+
+/// How `Interface` answers comparisons.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Comparator {
+    /// The natural order of the values.
+    Consistent,
+    /// A random answer to every comparison.
+    Random,
+    /// Rock-paper-scissors on the values modulo 3, which is not transitive.
+    NonTransitive,
+    /// The natural order for a number of comparisons, the reverse afterwards.
+    LiesAfter,
+    /// The natural order of keys shared by runs of consecutive values, which
+    /// tells stable methods from unstable ones.
+    Keyed,
+}
+
+impl Comparator {
+    pub const ALL: [Self; 5] = [
+        Self::Consistent,
+        Self::Random,
+        Self::NonTransitive,
+        Self::LiesAfter,
+        Self::Keyed,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Consistent => "consistent",
+            Self::Random => "random",
+            Self::NonTransitive => "non-transitive",
+            Self::LiesAfter => "lies-after",
+            Self::Keyed => "keyed",
+        }
+    }
+
+    /// Whether `data` is in order, by key when `Keyed` with `per_key` values to
+    /// a key.
+    pub fn is_sorted(self, data: &[u32], per_key: u64) -> bool {
+        match self {
+            Self::Keyed => data.is_sorted_by_key(|&v| key(v, per_key)),
+            _ => data.is_sorted(),
+        }
+    }
+}
+
+/// Key of `value` when `per_key` consecutive values share one.
+fn key(value: u32, per_key: u64) -> u64 {
+    u64::from(value) / per_key.max(1)
+}
+
+/// Per-run state of a `Comparator`, shared by all threads of the run.
+pub struct Judge {
+    comparator: Comparator,
+    /// Comparisons answered truthfully by `Comparator::LiesAfter`, or values
+    /// sharing a key under `Comparator::Keyed`.
+    after: u64,
+    calls: AtomicU64,
+    rng: Mutex<Option<StdRng>>,
+}
+
+impl Judge {
+    pub const fn new(comparator: Comparator, after: u64) -> Self {
+        Self {
+            comparator,
+            after,
+            calls: AtomicU64::new(0),
+            rng: Mutex::new(None),
+        }
+    }
+
+    pub fn compare(&self, a: u32, b: u32) -> Ordering {
+        let calls = self.calls.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+        match self.comparator {
+            Comparator::Consistent => a.cmp(&b),
+            Comparator::Random => {
+                let mut slot = self.rng.lock().unwrap();
+                match slot.get_or_insert_with(rng).gen_range(0..3) {
+                    0 => Ordering::Less,
+                    1 => Ordering::Equal,
+                    _ => Ordering::Greater,
+                }
+            }
+            Comparator::NonTransitive => match (3 + a % 3 - b % 3) % 3 {
+                0 => a.cmp(&b),
+                1 => Ordering::Greater,
+                _ => Ordering::Less,
+            },
+            Comparator::LiesAfter => {
+                let truth = a.cmp(&b);
+                if calls > self.after {
+                    truth.reverse()
+                } else {
+                    truth
+                }
+            }
+            Comparator::Keyed => key(a, self.after).cmp(&key(b, self.after)),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::external;
+use crate::sorter::Counters;
+
+/// Price of each kind of operation, in units of virtual time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Costs {
+    pub read: u64,
+    pub compare: u64,
+    pub swap: u64,
+    pub write: u64,
+    /// Per page read or written by the simulated disk.
+    pub page: u64,
+}
+
+impl Costs {
+    /// Named cost models, the default first.
+    pub const PRESETS: [(&'static str, Self); 4] = [
+        (
+            "uniform",
+            Self {
+                read: 1,
+                compare: 1,
+                swap: 1,
+                write: 1,
+                page: 0,
+            },
+        ),
+        // Writes to flash memory are slow and wear it out.
+        (
+            "flash",
+            Self {
+                read: 1,
+                compare: 1,
+                swap: 20,
+                write: 20,
+                page: 0,
+            },
+        ),
+        // Comparing long keys that share prefixes.
+        (
+            "strings",
+            Self {
+                read: 1,
+                compare: 20,
+                swap: 1,
+                write: 1,
+                page: 0,
+            },
+        ),
+        // A page transfer dwarfs any work in memory.
+        (
+            "disk",
+            Self {
+                read: 1,
+                compare: 1,
+                swap: 1,
+                write: 1,
+                page: 1000,
+            },
+        ),
+    ];
+
+    /// Name of the preset with these costs, if any.
+    pub fn name(self) -> Option<&'static str> {
+        Self::PRESETS
+            .iter()
+            .find(|(_, costs)| *costs == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// Virtual time taken by the operations in `counters` and the page
+    /// transfers in `pages`.
+    pub fn time(self, counters: Counters, pages: Option<external::Stats>) -> u64 {
+        let pages = pages.map_or(0, |pages| pages.reads + pages.writes);
+        counters.reads * self.read
+            + counters.compares * self.compare
+            + counters.swaps * self.swap
+            + counters.writes * self.write
+            + pages * self.page
+    }
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Self::PRESETS[0].1
+    }
+}
@@ -0,0 +1,337 @@
+use crate::MAX_DATA_SIZE;
+
+/// Largest element of a linearly scaled dataset; the smallest is 1.
+const LEVELS: u32 = 1_000_000;
+
+/// How the values of a dataset are turned into elements of the array.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    /// Map the range of the values linearly onto `1..=LEVELS`, keeping the
+    /// shape of their distribution. Values too close together become equal.
+    Linear,
+    /// Replace each value by its rank among the distinct values, so that bars
+    /// rise evenly whatever the distribution.
+    Rank,
+}
+
+impl Scale {
+    pub const ALL: [Self; 2] = [Self::Linear, Self::Rank];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Rank => "rank",
+        }
+    }
+}
+
+/// Values read from a file, before scaling.
+#[derive(Clone)]
+enum Values {
+    Numbers(Vec<f64>),
+    /// Text that is not all numbers, ordered lexicographically.
+    Text(Vec<String>),
+}
+
+/// A named array of values loaded from a file.
+#[derive(Clone)]
+pub struct Dataset {
+    pub name: String,
+    values: Values,
+}
+
+impl Dataset {
+    /// Parses the contents of the file `name`, by its extension: a column of a
+    /// `.csv` or `.tsv` file, a flat `.json` array of numbers or strings, or else
+    /// whitespace separated numbers or, failing that, lines of text.
+    ///
+    /// `column` picks the column of a table by header name or 1-based index,
+    /// the first being the default.
+    pub fn parse(name: &str, bytes: &[u8], column: Option<&str>) -> Result<Self, String> {
+        let text = std::str::from_utf8(bytes).map_err(|_| String::from("not UTF-8 text"))?;
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase());
+        let fields = match extension.as_deref() {
+            Some("csv") => table(text, ',', column)?,
+            Some("tsv") => table(text, '\t', column)?,
+            Some("json") => json_array(text)?,
+            _ => {
+                let words = text
+                    .split_whitespace()
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>();
+                match numbers(&words) {
+                    Some(_) => words,
+                    None => text
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(str::to_owned)
+                        .collect(),
+                }
+            }
+        };
+        if fields.is_empty() {
+            return Err(String::from("no values"));
+        }
+        if fields.len() > MAX_DATA_SIZE as usize {
+            return Err(format!("more than {MAX_DATA_SIZE} values"));
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            values: numbers(&fields).map_or(Values::Text(fields), Values::Numbers),
+        })
+    }
+
+    /// Reads and parses the file at `path`.
+    pub fn load(path: &str, column: Option<&str>) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+        let name = std::path::Path::new(path).file_name().map_or_else(
+            || path.to_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Self::parse(&name, &bytes, column).map_err(|err| format!("{path}: {err}"))
+    }
+
+    /// The elements of the array, in file order. Text is always ranked.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn data(&self, scale: Scale) -> Vec<u32> {
+        match (&self.values, scale) {
+            (Values::Numbers(values), Scale::Linear) => {
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let range = max - min;
+                values
+                    .iter()
+                    .map(|&v| {
+                        if range > 0.0 {
+                            1 + ((v - min) / range * f64::from(LEVELS - 1)).round() as u32
+                        } else {
+                            1
+                        }
+                    })
+                    .collect()
+            }
+            (Values::Numbers(values), Scale::Rank) => rank(values, f64::total_cmp),
+            (Values::Text(values), _) => rank(values, Ord::cmp),
+        }
+    }
+}
+
+/// Each value's rank, from 1, among the distinct `values`.
+fn rank<T>(values: &[T], cmp: impl Fn(&T, &T) -> std::cmp::Ordering) -> Vec<u32> {
+    let mut distinct = values.iter().collect::<Vec<_>>();
+    distinct.sort_by(|a, b| cmp(a, b));
+    distinct.dedup_by(|a, b| cmp(a, b).is_eq());
+    values
+        .iter()
+        .map(|v| {
+            let k = distinct.partition_point(|d| cmp(d, v).is_lt());
+            u32::try_from(k).unwrap() + 1
+        })
+        .collect()
+}
+
+/// `fields` as finite numbers, if they all are.
+fn numbers(fields: &[String]) -> Option<Vec<f64>> {
+    fields
+        .iter()
+        .map(|field| field.trim().parse().ok().filter(|v: &f64| v.is_finite()))
+        .collect()
+}
+
+/// The fields of one column of a table separated by `separator`. The first
+/// row is taken for a header when `column` names one or when its field is not
+/// a number.
+fn table(text: &str, separator: char, column: Option<&str>) -> Result<Vec<String>, String> {
+    let mut rows = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| split_row(line, separator));
+    let Some(first) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let (index, named) = match column {
+        Some(spec) => match spec.parse::<usize>() {
+            Ok(0) => return Err(String::from("columns are numbered from 1")),
+            Ok(n) => (n - 1, false),
+            Err(_) => {
+                let index = first
+                    .iter()
+                    .position(|name| name.trim() == spec)
+                    .ok_or_else(|| format!("no column named {spec}"))?;
+                (index, true)
+            }
+        },
+        None => (0, false),
+    };
+    let field = |row: &[String], line| {
+        row.get(index)
+            .cloned()
+            .ok_or_else(|| format!("line {line} has no column {}", index + 1))
+    };
+    let mut fields = Vec::new();
+    let first_field = field(&first, 1)?;
+    if !named && numbers(std::slice::from_ref(&first_field)).is_some() {
+        fields.push(first_field);
+    }
+    for (k, row) in rows.enumerate() {
+        fields.push(field(&row, k + 2)?);
+    }
+    Ok(fields)
+}
+
+/// Splits a line of a table, honoring double quotes around fields.
+fn split_row(line: &str, separator: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// The elements of a JSON array of numbers and strings, as text.
+fn json_array(text: &str) -> Result<Vec<String>, String> {
+    let mut chars = text.trim().chars().peekable();
+    if chars.next() != Some('[') {
+        return Err(String::from("expected a JSON array"));
+    }
+    let mut fields = Vec::new();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            Some(']') if fields.is_empty() => {
+                chars.next();
+                break;
+            }
+            Some('"') => {
+                chars.next();
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => field.push('\n'),
+                            Some('t') => field.push('\t'),
+                            Some('r') => field.push('\r'),
+                            Some('u') => {
+                                let hex = chars.by_ref().take(4).collect::<String>();
+                                let c = u32::from_str_radix(&hex, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or_else(|| format!("invalid escape \\u{hex}"))?;
+                                field.push(c);
+                            }
+                            Some(c) => field.push(c),
+                            None => return Err(String::from("unterminated string")),
+                        },
+                        Some(c) => field.push(c),
+                        None => return Err(String::from("unterminated string")),
+                    }
+                }
+                fields.push(field);
+            }
+            Some(_) => {
+                let mut field = String::new();
+                while let Some(c) = chars.next_if(|&c| c != ',' && c != ']') {
+                    field.push(c);
+                }
+                let field = field.trim().to_owned();
+                if numbers(std::slice::from_ref(&field)).is_none() {
+                    return Err(format!("not a number or string: {field}"));
+                }
+                fields.push(field);
+            }
+            None => return Err(String::from("unterminated array")),
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            Some(',') => {}
+            Some(']') => break,
+            _ => return Err(String::from("expected , or ] in array")),
+        }
+    }
+    if chars.any(|c| !c.is_whitespace()) {
+        return Err(String::from("trailing characters after the array"));
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|&field| field.to_owned()).collect()
+    }
+
+    #[test]
+    fn splits_quoted_rows() {
+        assert_eq!(split_row("a,b,c", ','), strings(&["a", "b", "c"]));
+        assert_eq!(
+            split_row(r#"a,"b,c","d""e","#, ','),
+            strings(&["a", "b,c", "d\"e", ""])
+        );
+        assert_eq!(split_row("\"x\ty\"\tz", '\t'), strings(&["x\ty", "z"]));
+        assert_eq!(split_row("", ','), strings(&[""]));
+    }
+
+    #[test]
+    fn parses_json_arrays() {
+        assert_eq!(json_array(" [] "), Ok(Vec::new()));
+        assert_eq!(
+            json_array(r#"[1, "a\"b", "\u0041\n", -2.5e3]"#),
+            Ok(strings(&["1", "a\"b", "A\n", "-2.5e3"]))
+        );
+        assert_eq!(
+            json_array("[1] x"),
+            Err(String::from("trailing characters after the array"))
+        );
+        for text in ["{}", "[1,]", "[true]", "[1", r#"["a"#, r#"["\u00zz"]"#] {
+            assert!(json_array(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn detects_headers() {
+        let headed = "x,y\n1,2\n3,4";
+        assert_eq!(table(headed, ',', None), Ok(strings(&["1", "3"])));
+        assert_eq!(table(headed, ',', Some("y")), Ok(strings(&["2", "4"])));
+        assert_eq!(table(headed, ',', Some("2")), Ok(strings(&["2", "4"])));
+        let bare = "5,6\n1,2";
+        assert_eq!(table(bare, ',', None), Ok(strings(&["5", "1"])));
+        assert_eq!(table(bare, ',', Some("2")), Ok(strings(&["6", "2"])));
+        assert!(table(bare, ',', Some("0")).is_err());
+        assert!(table(bare, ',', Some("z")).is_err());
+        assert_eq!(
+            table("1,2\n3", ',', Some("2")),
+            Err(String::from("line 2 has no column 2"))
+        );
+    }
+
+    #[test]
+    fn ranks_and_scales() {
+        assert_eq!(rank(&[3.0, 1.0, 3.0, 2.0], f64::total_cmp), [3, 1, 3, 2]);
+        let text = Dataset::parse("fruit.txt", b"pear\napple\npear\nfig", None).unwrap();
+        assert_eq!(text.data(Scale::Linear), [3, 1, 3, 2]);
+        let numbers = Dataset::parse("values.txt", b"0 5 10", None).unwrap();
+        assert_eq!(numbers.data(Scale::Linear), [1, 500_001, LEVELS]);
+        assert_eq!(numbers.data(Scale::Rank), [1, 2, 3]);
+        let column = Dataset::parse("table.csv", b"a,b\n2,x\n1,y", Some("a")).unwrap();
+        assert_eq!(column.data(Scale::Rank), [2, 1]);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::sorter::{State, Step};
+use std::fmt;
+
+/// Most recent changes kept for stepping back.
+const JOURNAL_LEN: usize = 10_000;
+
+/// A condition that pauses the run once it holds.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// The index takes part in a swap.
+    Swap(usize),
+    /// A swap or write leaves `value` at `position`.
+    Value { value: u32, position: usize },
+    /// The `n`th comparison has been made.
+    Compares(u64),
+    /// The method enters a different phase.
+    Phase,
+}
+
+impl Breakpoint {
+    pub const KINDS: [Self; 4] = [
+        Self::Swap(0),
+        Self::Value {
+            value: 1,
+            position: 0,
+        },
+        Self::Compares(1),
+        Self::Phase,
+    ];
+
+    pub const fn kind(self) -> &'static str {
+        match self {
+            Self::Swap(_) => "swap at index",
+            Self::Value { .. } => "value at position",
+            Self::Compares(_) => "after comparisons",
+            Self::Phase => "phase change",
+        }
+    }
+
+    /// Whether `step`, which just produced `state`, triggers this breakpoint.
+    /// `compares` is the comparison count before that operation.
+    fn hit(self, state: &State, step: &Step, compares: u64) -> bool {
+        match (self, step) {
+            (Self::Swap(i), Step::Swap(a, b)) => i == *a || i == *b,
+            (Self::Value { value, position }, Step::Swap(a, b)) => {
+                (position == *a || position == *b) && state.get(position) == Some(value)
+            }
+            (Self::Value { value, position }, Step::Write(a)) => {
+                position == *a && state.get(position) == Some(value)
+            }
+            (Self::Compares(n), _) => compares < n && state.counters.compares >= n,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Swap(i) => write!(f, "swap at index {i}"),
+            Self::Value { value, position } => write!(f, "value {value} at position {position}"),
+            Self::Compares(n) => write!(f, "after {n} comparisons"),
+            Self::Phase => write!(f, "phase change"),
+        }
+    }
+}
+
+/// A reversible change to the array by the thread of `cursor`, recorded for
+/// stepping back.
+#[derive(Clone, Copy)]
+pub enum Change {
+    Swap {
+        cursor: usize,
+        i: usize,
+        j: usize,
+    },
+    /// `value` replaced `old` at `i`.
+    Write {
+        cursor: usize,
+        i: usize,
+        old: u32,
+        value: u32,
+    },
+}
+
+impl Change {
+    fn apply(self, state: &mut State) {
+        match self {
+            Self::Swap { cursor, i, j } => {
+                state.swap(i, j);
+                if let Some(Some(cursor)) = state.cursors.get_mut(cursor) {
+                    cursor.step = Some(Step::Swap(i, j));
+                }
+            }
+            Self::Write {
+                cursor, i, value, ..
+            } => {
+                state.set(i, value);
+                if let Some(Some(cursor)) = state.cursors.get_mut(cursor) {
+                    cursor.step = Some(Step::Write(i));
+                }
+            }
+        }
+    }
+
+    fn revert(self, state: &mut State) {
+        match self {
+            // Swaps are their own inverse.
+            Self::Swap { .. } => self.apply(state),
+            Self::Write {
+                cursor,
+                i,
+                old,
+                value,
+            } => Self::Write {
+                cursor,
+                i,
+                old: value,
+                value: old,
+            }
+            .apply(state),
+        }
+    }
+}
+
+impl State {
+    /// Looks for a breakpoint triggered by `step`, the last operation, and
+    /// remembers it in `hit` as the reason for pausing.
+    pub fn check_breakpoints(&mut self, step: &Step, compares: u64) -> bool {
+        self.hit = self
+            .breakpoints
+            .iter()
+            .copied()
+            .find(|b| b.hit(self, step, compares));
+        self.hit.is_some()
+    }
+
+    pub fn record(&mut self, change: Change) {
+        if self.journal.len() == JOURNAL_LEN {
+            self.journal.pop_front();
+        }
+        self.journal.push_back(change);
+        self.redo.clear();
+    }
+
+    /// Undoes the most recent change. Returns `false` if there is none left.
+    pub fn step_back(&mut self) -> bool {
+        let Some(change) = self.journal.pop_back() else {
+            return false;
+        };
+        change.revert(self);
+        self.redo.push(change);
+        true
+    }
+
+    /// Redoes the most recently undone change. Returns `false` if there is none.
+    pub fn step_forward(&mut self) -> bool {
+        let Some(change) = self.redo.pop() else {
+            return false;
+        };
+        change.apply(self);
+        self.journal.push_back(change);
+        true
+    }
+}
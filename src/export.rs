@@ -0,0 +1,212 @@
+use crate::dataset::{Dataset, Scale};
+use crate::sorter::{Limits, Method, Sorter};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+
+/// How arrays are written.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single array as a `value` column; a sequence as one row per array,
+    /// led by the operation count.
+    Csv,
+    /// A single array as a JSON array; a sequence as one object per line.
+    Json,
+}
+
+impl Format {
+    /// JSON for `.json` files, CSV otherwise.
+    pub fn of(path: &str) -> Self {
+        if path.to_ascii_lowercase().ends_with(".json") {
+            Self::Json
+        } else {
+            Self::Csv
+        }
+    }
+}
+
+/// `data` in `format`, readable again as a dataset.
+pub fn array(data: &[u32], format: Format) -> String {
+    let values = data.iter().map(u32::to_string).collect::<Vec<_>>();
+    match format {
+        Format::Csv => format!("value\n{}\n", values.join("\n")),
+        Format::Json => format!("[{}]\n", values.join(", ")),
+    }
+}
+
+/// Writes `contents` to `path`.
+pub fn save(path: &str, contents: &str) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|err| format!("cannot write {path}: {err}"))
+}
+
+/// Writes the array of a run as it goes: at the start, every `every`
+/// operations and at the end. Nothing is kept once written, so long runs do
+/// not grow memory.
+pub struct Recorder {
+    /// File the arrays are written to, instead of stdout.
+    path: Option<String>,
+    format: Format,
+    every: u64,
+    out: Option<BufWriter<Box<dyn Write + Send>>>,
+    /// Operation count of the last array written in the current run.
+    last: Option<u64>,
+    /// First error of the current run.
+    error: Option<String>,
+    /// How the last finished run was written, until taken.
+    outcome: Option<Result<(), String>>,
+}
+
+impl Recorder {
+    pub fn new(path: Option<String>, format: Format, every: u64) -> Self {
+        Self {
+            path,
+            format,
+            every: every.max(1),
+            out: None,
+            last: None,
+            error: None,
+            outcome: None,
+        }
+    }
+
+    pub const fn every(&self) -> u64 {
+        self.every
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Opens the output and writes `data`, the array before the run.
+    pub fn begin(&mut self, data: &[u32]) {
+        self.last = None;
+        self.error = None;
+        let out: io::Result<Box<dyn Write + Send>> = match &self.path {
+            Some(path) => File::create(path).map(|file| Box::new(file) as _),
+            None => Ok(Box::new(io::stdout())),
+        };
+        match out {
+            Ok(out) => self.out = Some(BufWriter::new(out)),
+            Err(err) => self.fail(&err),
+        }
+        if self.format == Format::Csv {
+            let mut header = String::from("op");
+            for i in 0..data.len() {
+                write!(header, ",{i}").unwrap();
+            }
+            self.write(&header);
+        }
+        self.record(0, data);
+    }
+
+    /// Writes `data` if `op` is a multiple of `every`.
+    pub fn step(&mut self, op: u64, data: &[u32]) {
+        if self.out.is_some() && op.is_multiple_of(self.every) {
+            self.record(op, data);
+        }
+    }
+
+    /// Writes `data` after the last operation `op` unless it already was, and
+    /// closes the output.
+    pub fn finish(&mut self, op: u64, data: &[u32]) {
+        if self.last != Some(op) {
+            self.record(op, data);
+        }
+        if let Some(mut out) = self.out.take() {
+            if let Err(err) = out.flush() {
+                self.fail(&err);
+            }
+        }
+        self.outcome = Some(self.error.take().map_or(Ok(()), Err));
+    }
+
+    /// How the last finished run was written, once.
+    pub fn take_outcome(&mut self) -> Option<Result<(), String>> {
+        self.outcome.take()
+    }
+
+    fn record(&mut self, op: u64, data: &[u32]) {
+        self.last = Some(op);
+        let values = data.iter().map(u32::to_string).collect::<Vec<_>>();
+        let line = match self.format {
+            Format::Csv => format!("{op},{}", values.join(",")),
+            Format::Json => format!("{{\"op\": {op}, \"data\": [{}]}}", values.join(", ")),
+        };
+        self.write(&line);
+    }
+
+    fn write(&mut self, line: &str) {
+        if let Some(out) = &mut self.out {
+            if let Err(err) = writeln!(out, "{line}") {
+                self.fail(&err);
+            }
+        }
+    }
+
+    /// Gives up on the current run after `err`.
+    fn fail(&mut self, err: &io::Error) {
+        self.out = None;
+        let target = self.path.as_deref().unwrap_or("stdout");
+        self.error
+            .get_or_insert_with(|| format!("cannot write {target}: {err}"));
+    }
+}
+
+/// What `sortvis states` runs and where it writes the arrays.
+pub struct Config {
+    pub method: &'static Method,
+    pub size: u32,
+    /// Values loaded from a file, used instead of generating `size` elements.
+    pub dataset: Option<Dataset>,
+    pub scale: Scale,
+    pub input: Option<&'static Method>,
+    /// Operations between two arrays written.
+    pub every: u64,
+    pub budget: Option<u64>,
+    pub format: Format,
+    /// File the arrays are written to, instead of stdout.
+    pub output: Option<String>,
+}
+
+/// Runs `config.method` without throttling and writes the array every
+/// `config.every` operations, the first and last included.
+///
+/// Returns whether the run finished sorted.
+pub fn run(config: &Config) -> Result<bool, String> {
+    let mut data = config.dataset.as_ref().map_or_else(
+        || (1..=config.size).collect(),
+        |dataset| dataset.data(config.scale),
+    );
+    if let Some(input) = config.input {
+        data = crate::sorter::apply(input, data);
+    }
+
+    let mut sorter = Sorter::new(data);
+    sorter.method = Some(config.method);
+    sorter.throttled = false;
+    sorter.limits = Limits {
+        budget: config.budget,
+        timeout: None,
+    };
+    let recorder = Arc::new(Mutex::new(Recorder::new(
+        config.output.clone(),
+        config.format,
+        config.every,
+    )));
+    sorter.recorder = Some(recorder.clone());
+    sorter.start(true);
+    sorter.wait();
+
+    if let Some(outcome) = recorder.lock().unwrap().take_outcome() {
+        outcome?;
+    }
+    let mut state = sorter.state.lock().unwrap();
+    if let Some(failure) = state.failure.take() {
+        return Err(failure.to_string());
+    }
+    if let Some(abort) = state.aborted {
+        eprintln!("{}: {abort}", config.method.name);
+    }
+    Ok(state.data.is_sorted())
+}
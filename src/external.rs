@@ -0,0 +1,223 @@
+use crate::graph;
+use crate::sorter::Step;
+use egui::{pos2, Align2, Color32, FontId, Mesh, Painter, Rect};
+
+const CLEAN: Color32 = Color32::from_rgb(30, 70, 40);
+const DIRTY: Color32 = Color32::from_rgb(110, 60, 20);
+const LABEL: Color32 = Color32::GRAY;
+
+/// Size of the simulated memory and of the pages moved between it and disk.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Elements per page.
+    pub page: usize,
+    /// Pages that fit in memory at once.
+    pub frames: usize,
+}
+
+impl Config {
+    /// Fewest frames the external methods can merge with.
+    pub const MIN_FRAMES: usize = 3;
+}
+
+impl Default for Config {
+    /// Small enough for the default array to take several merge passes.
+    fn default() -> Self {
+        Self { page: 4, frames: 4 }
+    }
+}
+
+/// Pages moved between memory and disk.
+#[derive(Clone, Copy, Default)]
+pub struct Stats {
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} page reads, {} page writes", self.reads, self.writes)
+    }
+}
+
+/// A page of memory and whether it has changed since it was read.
+#[derive(Clone, Copy)]
+struct Frame {
+    file: usize,
+    page: usize,
+    dirty: bool,
+}
+
+/// The array and any scratch files of a run, kept on a simulated disk.
+///
+/// Elements are addressed as in the array, with scratch file `f` following it
+/// at `f * len`. Touching an element outside memory reads its page, first
+/// evicting the least recently used one if memory is full. Methods written for
+/// the model evict and discard pages themselves so this never happens.
+#[derive(Clone)]
+pub struct Disk {
+    pub config: Config,
+    /// Length of the array and of every scratch file.
+    len: usize,
+    /// Scratch files; their unused elements are 0.
+    pub files: Vec<Vec<u32>>,
+    /// Pages in memory, least recently used first.
+    frames: Vec<Frame>,
+    pub stats: Stats,
+}
+
+impl Disk {
+    pub const fn new(config: Config, len: usize) -> Self {
+        Self {
+            config,
+            len,
+            files: Vec::new(),
+            frames: Vec::new(),
+            stats: Stats {
+                reads: 0,
+                writes: 0,
+            },
+        }
+    }
+
+    /// Makes sure there are at least `files` scratch files.
+    pub fn open(&mut self, files: usize) {
+        while self.files.len() < files {
+            self.files.push(vec![0; self.len]);
+        }
+    }
+
+    /// Number of addressable elements, array included.
+    pub const fn extent(&self) -> usize {
+        self.len * (1 + self.files.len())
+    }
+
+    /// File and page holding the element at `address`.
+    const fn locate(&self, address: usize) -> (usize, usize) {
+        let len = if self.len == 0 { 1 } else { self.len };
+        (address / len, address % len / self.config.page)
+    }
+
+    /// Brings the pages of every element `step` touches into memory.
+    pub fn step(&mut self, step: &Step) {
+        match *step {
+            Step::Read(i) => self.touch(i, false),
+            Step::Compare(i, j) => {
+                self.touch(i, false);
+                self.touch(j, false);
+            }
+            Step::Swap(i, j) => {
+                self.touch(i, true);
+                self.touch(j, true);
+            }
+            Step::Write(i) => self.touch(i, true),
+        }
+    }
+
+    /// Brings the page holding `address` into memory, marking it dirty if
+    /// `write` is set.
+    fn touch(&mut self, address: usize, write: bool) {
+        if address >= self.extent() {
+            return;
+        }
+        let (file, page) = self.locate(address);
+        if !self.frames.iter().any(|f| (f.file, f.page) == (file, page)) {
+            self.claim(address);
+            self.stats.reads += 1;
+        }
+        // The page is the most recently used frame from now on.
+        let k = self
+            .frames
+            .iter()
+            .position(|f| (f.file, f.page) == (file, page))
+            .unwrap();
+        let mut frame = self.frames.remove(k);
+        frame.dirty |= write;
+        self.frames.push(frame);
+    }
+
+    /// Gives the page holding `address` a frame without reading it, for a page
+    /// about to be overwritten.
+    pub fn claim(&mut self, address: usize) {
+        let (file, page) = self.locate(address);
+        if address >= self.extent() || self.frames.iter().any(|f| (f.file, f.page) == (file, page))
+        {
+            return;
+        }
+        if self.frames.len() >= self.config.frames {
+            let victim = self.frames.remove(0);
+            self.stats.writes += u64::from(victim.dirty);
+        }
+        self.frames.push(Frame {
+            file,
+            page,
+            dirty: false,
+        });
+    }
+
+    /// Removes the page holding `address` from memory, writing it back if it
+    /// changed and `write` is set.
+    pub fn evict(&mut self, address: usize, write: bool) {
+        let (file, page) = self.locate(address);
+        if let Some(k) = self
+            .frames
+            .iter()
+            .position(|f| (f.file, f.page) == (file, page))
+        {
+            let frame = self.frames.remove(k);
+            self.stats.writes += u64::from(write && frame.dirty);
+        }
+    }
+
+    /// Writes back every changed page, as at the end of a run.
+    pub fn flush(&mut self) {
+        for frame in self.frames.drain(..) {
+            self.stats.writes += u64::from(frame.dirty);
+        }
+    }
+
+    /// Draws the array and every scratch file as a bar graph of its own, on top
+    /// of the pages in memory.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn draw(&self, painter: &Painter, area: Rect, data: &[u32]) {
+        let files = 1 + self.files.len();
+        let row_h = area.height() / files as f32;
+        let page_w = area.width() * self.config.page as f32 / self.len.max(1) as f32;
+        for file in 0..files {
+            let top = (file as f32).mul_add(row_h, area.min.y);
+            let row = Rect::from_min_max(
+                pos2(area.min.x, top + 2.0),
+                pos2(area.max.x, top + row_h - 2.0),
+            );
+            let mut mesh = Mesh::default();
+            for frame in self.frames.iter().filter(|f| f.file == file) {
+                let x = (frame.page as f32).mul_add(page_w, row.min.x);
+                mesh.add_colored_rect(
+                    Rect::from_min_max(
+                        pos2(x, row.min.y),
+                        pos2((x + page_w).min(row.max.x), row.max.y),
+                    ),
+                    if frame.dirty { DIRTY } else { CLEAN },
+                );
+            }
+            painter.add(mesh);
+            let contents = if file == 0 {
+                data
+            } else {
+                &self.files[file - 1]
+            };
+            graph::draw_bars(painter, row, contents, &[]);
+            painter.text(
+                row.left_top(),
+                Align2::LEFT_TOP,
+                if file == 0 {
+                    String::from("array")
+                } else {
+                    format!("file {file}")
+                },
+                FontId::proportional(12.0),
+                LABEL,
+            );
+        }
+    }
+}
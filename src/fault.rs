@@ -0,0 +1,85 @@
+use crate::methods::rng;
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A fault injected into the `Interface` operations of a run.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    None,
+    /// The `n`th element loaded, by a read or a comparison, has a wrong value.
+    WrongRead,
+    /// The `n`th swap silently does nothing.
+    NoopSwap,
+    /// Panics at a random operation among the first `n`.
+    Panic,
+}
+
+impl Fault {
+    pub const ALL: [Self; 4] = [Self::None, Self::WrongRead, Self::NoopSwap, Self::Panic];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::WrongRead => "wrong-read",
+            Self::NoopSwap => "noop-swap",
+            Self::Panic => "panic",
+        }
+    }
+}
+
+/// Per-run state of a `Fault`, shared by all threads of the run.
+pub struct Injector {
+    fault: Fault,
+    n: u64,
+    reads: AtomicU64,
+    swaps: AtomicU64,
+    ops: AtomicU64,
+}
+
+impl Injector {
+    pub fn new(fault: Fault, n: u64) -> Self {
+        let n = match fault {
+            Fault::Panic => rng().gen_range(1..=n.max(1)),
+            _ => n,
+        };
+        Self {
+            fault,
+            n,
+            reads: AtomicU64::new(0),
+            swaps: AtomicU64::new(0),
+            ops: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts an operation, panicking if it is the faulty one.
+    #[track_caller]
+    pub fn operation(&self) {
+        let ops = self.ops.fetch_add(1, Ordering::Relaxed) + 1;
+        assert!(
+            self.fault != Fault::Panic || ops != self.n,
+            "injected fault at operation {}",
+            self.n
+        );
+    }
+
+    /// Returns `value`, or a different random one if this load is the faulty one.
+    pub fn read(&self, value: u32) -> u32 {
+        let reads = self.reads.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.fault == Fault::WrongRead && reads == self.n {
+            let wrong = rng().gen::<u32>();
+            if wrong == value {
+                wrong.wrapping_add(1)
+            } else {
+                wrong
+            }
+        } else {
+            value
+        }
+    }
+
+    /// Whether this swap should actually be performed.
+    pub fn swap(&self) -> bool {
+        let swaps = self.swaps.fetch_add(1, Ordering::Relaxed) + 1;
+        !(self.fault == Fault::NoopSwap && swaps == self.n)
+    }
+}
@@ -0,0 +1,434 @@
+use crate::comparator::Comparator;
+use crate::json::quote;
+use crate::methods::MODIFIERS;
+use crate::sorter::{Method, Sorter};
+use std::fmt::Write as _;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Values sharing a key in the stability check.
+const PER_KEY: u64 = 4;
+
+/// How the report is written.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Junit,
+}
+
+/// What `sortvis grade` checks and where it reports.
+pub struct Config {
+    pub method: &'static Method,
+    /// Method whose operation counts bound those of `method`.
+    pub reference: &'static Method,
+    /// Most operations allowed, as a multiple of the reference's.
+    pub factor: f64,
+    pub sizes: Vec<u32>,
+    /// Whether `method` is held to being stable.
+    pub stable: bool,
+    pub seed: u64,
+    pub timeout: Duration,
+    pub format: Format,
+    /// File the report is written to, instead of stdout.
+    pub output: Option<String>,
+}
+
+/// Outcome of one check of one input.
+struct Case {
+    /// Generator of the input: "sorted" or a modifier.
+    input: &'static str,
+    size: u32,
+    check: &'static str,
+    /// Operations performed by the method and the most allowed, for the
+    /// operations check.
+    ops: Option<(u64, u64)>,
+    /// Why the check failed.
+    failure: Option<String>,
+    time: Duration,
+}
+
+/// How a single run ended.
+struct Run {
+    data: Vec<u32>,
+    ops: u64,
+    /// Panic or timeout that cut the run short.
+    error: Option<String>,
+    time: Duration,
+}
+
+/// Runs `config.method` over every generator at every size, checking that it
+/// sorts, stays within the operation bound and, if held to it, is stable.
+///
+/// Writes the report and returns whether every check passed.
+pub fn run(config: &Config) -> Result<bool, String> {
+    let inputs =
+        std::iter::once(("sorted", None)).chain(MODIFIERS.iter().map(|m| (m.name, Some(m))));
+    let mut cases = Vec::new();
+    for (input, modifier) in inputs {
+        for &size in &config.sizes {
+            let mut data = (1..=size).collect::<Vec<u32>>();
+            if let Some(modifier) = modifier {
+                data = crate::sorter::apply(modifier, data);
+            }
+            check(config, input, &data, &mut cases);
+        }
+    }
+
+    let report = match config.format {
+        Format::Json => json(config, &cases),
+        Format::Junit => junit(config, &cases),
+    };
+    match &config.output {
+        Some(path) => {
+            std::fs::write(path, report).map_err(|err| format!("cannot write {path}: {err}"))?;
+        }
+        None => print!("{report}"),
+    }
+    let failed = cases.iter().filter(|c| c.failure.is_some()).count();
+    eprintln!(
+        "{}: {} of {} checks passed",
+        config.method.name,
+        cases.len() - failed,
+        cases.len()
+    );
+    Ok(failed == 0)
+}
+
+/// Checks the method on `data`, made by the generator `input`.
+fn check(config: &Config, input: &'static str, data: &[u32], cases: &mut Vec<Case>) {
+    let size = u32::try_from(data.len()).unwrap();
+    let case = |check, ops, failure, time| Case {
+        input,
+        size,
+        check,
+        ops,
+        failure,
+        time,
+    };
+
+    let run = execute(config.method, data, Comparator::Consistent, config.timeout);
+    let mut expected = data.to_vec();
+    expected.sort_unstable();
+    let failure = run.error.clone().or_else(|| {
+        let mut output = run.data.clone();
+        output.sort_unstable();
+        if output != expected {
+            Some(String::from("not a permutation of the input"))
+        } else if !run.data.is_sorted() {
+            Some(String::from("not sorted"))
+        } else {
+            None
+        }
+    });
+    let sorted = failure.is_none();
+    cases.push(case("sorted", None, failure, run.time));
+
+    if sorted {
+        let reference = execute(
+            config.reference,
+            data,
+            Comparator::Consistent,
+            config.timeout,
+        );
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let bound = (reference.ops as f64 * config.factor).ceil() as u64;
+        let failure = match reference.error {
+            Some(error) => Some(format!(
+                "reference {} failed: {error}",
+                config.reference.name
+            )),
+            None if run.ops > bound => Some(format!(
+                "{} operations, more than {bound} ({} × {} by {})",
+                run.ops, config.factor, reference.ops, config.reference.name
+            )),
+            None => None,
+        };
+        cases.push(case(
+            "operations",
+            Some((run.ops, bound)),
+            failure,
+            run.time,
+        ));
+    }
+
+    if config.stable {
+        let run = execute(config.method, data, Comparator::Keyed, config.timeout);
+        let mut expected = data.to_vec();
+        expected.sort_by_key(|&v| u64::from(v) / PER_KEY);
+        let failure = run.error.or_else(|| {
+            run.data
+                .iter()
+                .zip(&expected)
+                .position(|(a, b)| a != b)
+                .map(|i| {
+                    format!(
+                        "{} at index {i} where a stable sort puts {}",
+                        run.data[i], expected[i]
+                    )
+                })
+        });
+        cases.push(case("stable", None, failure, run.time));
+    }
+}
+
+/// Runs `method` on `input` without throttling, stopping it after `timeout`.
+fn execute(
+    method: &'static Method,
+    input: &[u32],
+    comparator: Comparator,
+    timeout: Duration,
+) -> Run {
+    let mut sorter = Sorter::new(input.to_vec());
+    sorter.method = Some(method);
+    sorter.throttled = false;
+    sorter.comparator = comparator;
+    sorter.lie_after = PER_KEY;
+    let start = Instant::now();
+    sorter.start(false);
+
+    let mut error = None;
+    while sorter.is_sorting() {
+        if start.elapsed() >= timeout {
+            sorter.stop();
+            error = Some(format!("timed out after {} s", timeout.as_secs_f64()));
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    let time = start.elapsed();
+
+    let mut state = sorter.state.lock().unwrap();
+    if let Some(failure) = state.failure.take() {
+        error = Some(failure.to_string());
+    }
+    Run {
+        data: state.data.clone(),
+        ops: state.ops,
+        error,
+        time,
+    }
+}
+
+fn json(config: &Config, cases: &[Case]) -> String {
+    let failed = cases.iter().filter(|c| c.failure.is_some()).count();
+    let mut out = String::new();
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"method\": {},", quote(config.method.name)).unwrap();
+    writeln!(out, "  \"reference\": {},", quote(config.reference.name)).unwrap();
+    writeln!(out, "  \"factor\": {},", config.factor).unwrap();
+    writeln!(out, "  \"seed\": {},", config.seed).unwrap();
+    writeln!(out, "  \"passed\": {},", cases.len() - failed).unwrap();
+    writeln!(out, "  \"failed\": {failed},").unwrap();
+    writeln!(out, "  \"cases\": [").unwrap();
+    for (i, case) in cases.iter().enumerate() {
+        let mut fields = vec![
+            format!("\"input\": {}", quote(case.input)),
+            format!("\"size\": {}", case.size),
+            format!("\"check\": {}", quote(case.check)),
+            format!("\"passed\": {}", case.failure.is_none()),
+        ];
+        if let Some((ops, bound)) = case.ops {
+            fields.push(format!("\"ops\": {ops}, \"bound\": {bound}"));
+        }
+        if let Some(failure) = &case.failure {
+            fields.push(format!("\"message\": {}", quote(failure)));
+        }
+        fields.push(format!("\"time\": {:.6}", case.time.as_secs_f64()));
+        let comma = if i + 1 < cases.len() { "," } else { "" };
+        writeln!(out, "    {{{}}}{comma}", fields.join(", ")).unwrap();
+    }
+    writeln!(out, "  ]").unwrap();
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+fn junit(config: &Config, cases: &[Case]) -> String {
+    let failed = cases.iter().filter(|c| c.failure.is_some()).count();
+    let name = escape(config.method.name);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        out,
+        "<testsuites name=\"sortvis grade\" tests=\"{}\" failures=\"{failed}\">",
+        cases.len()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "  <testsuite name=\"{name}\" tests=\"{}\" failures=\"{failed}\">",
+        cases.len()
+    )
+    .unwrap();
+    for case in cases {
+        write!(
+            out,
+            "    <testcase classname=\"{name}.{}\" name=\"{} {}\" time=\"{:.6}\"",
+            escape(case.input),
+            case.check,
+            case.size,
+            case.time.as_secs_f64()
+        )
+        .unwrap();
+        match &case.failure {
+            Some(failure) => {
+                writeln!(out, ">").unwrap();
+                writeln!(out, "      <failure message=\"{}\"/>", escape(failure)).unwrap();
+                writeln!(out, "    </testcase>").unwrap();
+            }
+            None => writeln!(out, "/>").unwrap(),
+        }
+    }
+    writeln!(out, "  </testsuite>").unwrap();
+    writeln!(out, "</testsuites>").unwrap();
+    out
+}
+
+/// `s` escaped for an XML attribute.
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::METHODS;
+
+    fn method(name: &str) -> &'static Method {
+        METHODS.iter().find(|m| m.name == name).unwrap()
+    }
+
+    fn config(name: &str, reference: &str, factor: f64) -> Config {
+        Config {
+            method: method(name),
+            reference: method(reference),
+            factor,
+            sizes: Vec::new(),
+            stable: true,
+            seed: 0,
+            timeout: Duration::from_secs(10),
+            format: Format::Json,
+            output: None,
+        }
+    }
+
+    /// The checks of `config` on `data`, by name.
+    fn checks(config: &Config, data: &[u32]) -> Vec<(&'static str, Option<String>)> {
+        let mut cases = Vec::new();
+        check(config, "test", data, &mut cases);
+        cases.into_iter().map(|c| (c.check, c.failure)).collect()
+    }
+
+    #[test]
+    fn stable_method_passes() {
+        let data = (1..=40).rev().collect::<Vec<_>>();
+        let cases = checks(&config("insertion", "insertion", 1.0), &data);
+        assert_eq!(
+            cases,
+            [("sorted", None), ("operations", None), ("stable", None)]
+        );
+    }
+
+    #[test]
+    fn unstable_method_fails() {
+        // Building the heap reorders elements of equal keys in sorted input.
+        let data = (1..=12).collect::<Vec<_>>();
+        let cases = checks(&config("heap", "heap", 1.0), &data);
+        assert_eq!(cases[0], ("sorted", None));
+        let (check, failure) = &cases[2];
+        assert_eq!(*check, "stable");
+        assert!(failure
+            .as_ref()
+            .unwrap()
+            .contains("where a stable sort puts"));
+    }
+
+    #[test]
+    fn exceeding_the_bound_fails() {
+        let data = (1..=100).rev().collect::<Vec<_>>();
+        let cases = checks(&config("bubble", "heap", 2.0), &data);
+        assert_eq!(cases[0], ("sorted", None));
+        let (check, failure) = &cases[1];
+        assert_eq!(*check, "operations");
+        let failure = failure.as_ref().unwrap();
+        assert!(
+            failure.contains("more than") && failure.contains("by heap"),
+            "{failure}"
+        );
+    }
+
+    fn report() -> (Config, Vec<Case>) {
+        let config = config("bubble", "heap", 1.5);
+        let cases = vec![
+            Case {
+                input: "sorted",
+                size: 2,
+                check: "sorted",
+                ops: None,
+                failure: None,
+                time: Duration::from_millis(1),
+            },
+            Case {
+                input: "invert",
+                size: 2,
+                check: "operations",
+                ops: Some((9, 6)),
+                failure: Some(String::from("9 operations, \"more\" than <6>")),
+                time: Duration::from_micros(2500),
+            },
+        ];
+        (config, cases)
+    }
+
+    #[test]
+    fn json_report() {
+        let (config, cases) = report();
+        assert_eq!(
+            json(&config, &cases),
+            r#"{
+  "method": "bubble",
+  "reference": "heap",
+  "factor": 1.5,
+  "seed": 0,
+  "passed": 1,
+  "failed": 1,
+  "cases": [
+    {"input": "sorted", "size": 2, "check": "sorted", "passed": true, "time": 0.001000},
+    {"input": "invert", "size": 2, "check": "operations", "passed": false, "ops": 9, "bound": 6, "message": "9 operations, \"more\" than <6>", "time": 0.002500}
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn junit_report() {
+        let (config, cases) = report();
+        assert_eq!(
+            junit(&config, &cases),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="sortvis grade" tests="2" failures="1">
+  <testsuite name="bubble" tests="2" failures="1">
+    <testcase classname="bubble.sorted" name="sorted 2" time="0.001000"/>
+    <testcase classname="bubble.invert" name="operations 2" time="0.002500">
+      <failure message="9 operations, &quot;more&quot; than &lt;6&gt;"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
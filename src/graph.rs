@@ -0,0 +1,139 @@
+use crate::sorter::{Cursor, Step};
+use egui::{pos2, Color32, Mesh, Painter, Rect};
+
+const BAR: Color32 = Color32::LIGHT_BLUE;
+const RANGE: Color32 = Color32::from_rgb(60, 90, 120);
+const MEAN: Color32 = Color32::from_rgb(200, 230, 255);
+const READ: Color32 = Color32::GREEN;
+const SWAP: Color32 = Color32::RED;
+/// Swap colors of the cursors after the first; their reads are drawn dimmer.
+const CURSORS: [Color32; 6] = [
+    Color32::GOLD,
+    Color32::from_rgb(255, 120, 220),
+    Color32::from_rgb(80, 220, 220),
+    Color32::from_rgb(255, 150, 60),
+    Color32::from_rgb(170, 130, 255),
+    Color32::from_rgb(150, 255, 120),
+];
+
+/// Value statistics of the elements falling into one pixel column.
+struct Column {
+    min: u32,
+    max: u32,
+    mean: f32,
+}
+
+/// Draws `data` as a bar graph filling `area`.
+///
+/// With more elements than pixel columns the elements are binned per column,
+/// showing a bar up to the column minimum, the min..max range above it and a
+/// marker at the mean, so the amount of geometry depends on the window width
+/// rather than on `data.len()`.
+/// The last operation of every cursor is highlighted on top at full height
+/// either way, in a color of its own.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn draw_bars(painter: &Painter, area: Rect, data: &[u32], cursors: &[Option<Cursor>]) {
+    let len = data.len().max(1);
+    let mut mesh = Mesh::default();
+
+    let maxv = if data.len() as f32 <= area.width() {
+        let maxv = (*data.iter().max().unwrap_or(&1)).max(1) as f32;
+        let bar_w = area.width() / len as f32;
+        for (i, &v) in data.iter().enumerate() {
+            let x = (i as f32).mul_add(bar_w, area.min.x);
+            let h = area.height() * (v as f32 / maxv);
+            mesh.add_colored_rect(
+                Rect::from_min_max(
+                    pos2(x, area.max.y - h),
+                    pos2(x + (bar_w - 2.0).max(bar_w * 0.9), area.max.y),
+                ),
+                BAR,
+            );
+        }
+        maxv
+    } else {
+        let columns = bin(data, area.width() as usize);
+        let maxv = columns.iter().map(|c| c.max).max().unwrap_or(1).max(1) as f32;
+        let col_w = area.width() / columns.len() as f32;
+        for (i, column) in columns.iter().enumerate() {
+            let x = (i as f32).mul_add(col_w, area.min.x);
+            let y = |v: f32| area.height().mul_add(-(v / maxv), area.max.y);
+            mesh.add_colored_rect(
+                Rect::from_min_max(pos2(x, y(column.min as f32)), pos2(x + col_w, area.max.y)),
+                BAR,
+            );
+            mesh.add_colored_rect(
+                Rect::from_min_max(
+                    pos2(x, y(column.max as f32)),
+                    pos2(x + col_w, y(column.min as f32)),
+                ),
+                RANGE,
+            );
+            let mean = y(column.mean);
+            mesh.add_colored_rect(
+                Rect::from_min_max(pos2(x, mean - 1.0), pos2(x + col_w, mean + 1.0)),
+                MEAN,
+            );
+        }
+        maxv
+    };
+
+    let mut highlights = Vec::new();
+    for (k, cursor) in cursors.iter().enumerate() {
+        let Some(step) = cursor.as_ref().and_then(|cursor| cursor.step.as_ref()) else {
+            continue;
+        };
+        let (read, swap) = if k == 0 {
+            (READ, SWAP)
+        } else {
+            let color = CURSORS[(k - 1) % CURSORS.len()];
+            (color.gamma_multiply(0.6), color)
+        };
+        match *step {
+            Step::Read(i) => highlights.push((i, read)),
+            Step::Compare(i, j) => highlights.extend([(i, read), (j, read)]),
+            Step::Swap(i, j) => highlights.extend([(i, swap), (j, swap)]),
+            Step::Write(i) => highlights.push((i, swap)),
+        }
+    }
+    let bar_w = (area.width() / len as f32).max(1.0);
+    for (i, color) in highlights {
+        let Some(&v) = data.get(i) else { continue };
+        let x = (i as f32).mul_add(area.width() / len as f32, area.min.x);
+        let h = area.height() * (v as f32 / maxv.max(1.0));
+        mesh.add_colored_rect(
+            Rect::from_min_max(pos2(x, area.max.y - h), pos2(x + bar_w, area.max.y)),
+            color,
+        );
+    }
+
+    painter.add(mesh);
+}
+
+/// Splits `data` into `width` contiguous bins of nearly equal size.
+#[allow(clippy::cast_precision_loss)]
+fn bin(data: &[u32], width: usize) -> Vec<Column> {
+    let width = width.max(1);
+    (0..width)
+        .map(|c| {
+            let chunk = &data[c * data.len() / width..(c + 1) * data.len() / width];
+            let mut min = u32::MAX;
+            let mut max = 0;
+            let mut sum = 0u64;
+            for &v in chunk {
+                min = min.min(v);
+                max = max.max(v);
+                sum += u64::from(v);
+            }
+            Column {
+                min,
+                max,
+                mean: sum as f32 / chunk.len().max(1) as f32,
+            }
+        })
+        .collect()
+}
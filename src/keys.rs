@@ -0,0 +1,164 @@
+use crate::methods::MODIFIERS;
+use egui::{Event, Key, KeyboardShortcut, Modifiers};
+
+/// Something the controls do that can be bound to a key or run from the
+/// command palette.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    StartStop,
+    PauseResume,
+    Step,
+    StepBack,
+    SpeedUp,
+    SpeedDown,
+    NextMethod,
+    PreviousMethod,
+    /// Apply the modifier at this index of `MODIFIERS`.
+    Modifier(usize),
+    Generate,
+    Palette,
+    Settings,
+}
+
+impl Action {
+    /// Every action, in the order the settings list them.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            Self::StartStop,
+            Self::PauseResume,
+            Self::Step,
+            Self::StepBack,
+            Self::SpeedUp,
+            Self::SpeedDown,
+            Self::NextMethod,
+            Self::PreviousMethod,
+            Self::Generate,
+        ]
+        .into_iter()
+        .chain((0..MODIFIERS.len()).map(Self::Modifier))
+        .chain([Self::Palette, Self::Settings])
+    }
+
+    pub fn label(self) -> String {
+        String::from(match self {
+            Self::StartStop => "Start / stop",
+            Self::PauseResume => "Pause / resume",
+            Self::Step => "Step",
+            Self::StepBack => "Step back",
+            Self::SpeedUp => "Speed up",
+            Self::SpeedDown => "Slow down",
+            Self::NextMethod => "Next method",
+            Self::PreviousMethod => "Previous method",
+            Self::Modifier(i) => return format!("Modifier: {}", MODIFIERS[i].name),
+            Self::Generate => "Generate",
+            Self::Palette => "Command palette",
+            Self::Settings => "Keyboard shortcuts",
+        })
+    }
+
+    /// The key bound to the action until changed. Presentation clickers send
+    /// Page Down and Page Up, so those step.
+    fn default_key(self) -> Option<KeyboardShortcut> {
+        let plain = |key| Some(KeyboardShortcut::new(Modifiers::NONE, key));
+        match self {
+            Self::StartStop => plain(Key::S),
+            Self::PauseResume => plain(Key::Space),
+            Self::Step => plain(Key::PageDown),
+            Self::StepBack => plain(Key::PageUp),
+            Self::SpeedUp => plain(Key::ArrowUp),
+            Self::SpeedDown => plain(Key::ArrowDown),
+            Self::NextMethod => plain(Key::M),
+            Self::PreviousMethod => Some(KeyboardShortcut::new(Modifiers::SHIFT, Key::M)),
+            Self::Modifier(i) => [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5]
+                .get(i)
+                .and_then(|&key| plain(key)),
+            Self::Generate => plain(Key::G),
+            Self::Palette => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::P)),
+            Self::Settings => Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Comma)),
+        }
+    }
+}
+
+/// The key bound to each action, if any.
+pub struct Bindings {
+    pub keys: Vec<(Action, Option<KeyboardShortcut>)>,
+    /// Action waiting in the settings for its new key.
+    pub capturing: Option<Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::all()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+            capturing: None,
+        }
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, action: Action) -> Option<KeyboardShortcut> {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|&(_, key)| key)
+    }
+
+    /// Binds `key` to `action`, taking it from any other action.
+    pub fn bind(&mut self, action: Action, key: Option<KeyboardShortcut>) {
+        for (a, k) in &mut self.keys {
+            if *a == action {
+                *k = key;
+            } else if key.is_some() && *k == key {
+                *k = None;
+            }
+        }
+    }
+
+    /// The actions whose keys were pressed this frame, consuming the presses.
+    ///
+    /// While a text field has focus only shortcuts with Ctrl or Cmd count, and
+    /// while capturing a key nothing does.
+    pub fn pressed(&mut self, ctx: &egui::Context) -> Vec<Action> {
+        if let Some(action) = self.capturing {
+            let key = ctx.input(|i| {
+                i.events.iter().find_map(|event| match *event {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some(KeyboardShortcut::new(modifiers, key)),
+                    _ => None,
+                })
+            });
+            if let Some(key) = key {
+                if key.logical_key != Key::Escape {
+                    self.bind(action, Some(key));
+                }
+                self.capturing = None;
+            }
+            return Vec::new();
+        }
+
+        let typing = ctx.wants_keyboard_input();
+        let mut keys = self
+            .keys
+            .iter()
+            .filter_map(|&(action, key)| Some((action, key?)))
+            .filter(|(_, key)| !typing || key.modifiers.command)
+            .collect::<Vec<_>>();
+        // Extra Shift and Alt still match, so the most specific go first.
+        keys.sort_by_key(|(_, key)| {
+            let m = key.modifiers;
+            std::cmp::Reverse(u8::from(m.shift) + u8::from(m.alt) + u8::from(m.command))
+        });
+        ctx.input_mut(|i| {
+            keys.into_iter()
+                .filter(|(_, key)| i.consume_shortcut(key))
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
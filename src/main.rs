@@ -1,255 +1,1513 @@
 #![windows_subsystem = "windows"]
 
+mod cache;
+mod cli;
+mod comparator;
+mod cost;
+mod dataset;
+mod debugger;
+mod export;
+mod external;
+#[cfg(feature = "fallible")]
+mod fault;
+mod grade;
+mod graph;
+mod json;
+mod keys;
 mod methods;
+mod metrics;
+mod network;
+mod script;
+mod server;
 mod sorter;
+mod torture;
+mod trace;
+mod wasm;
 
+use cli::{Command, Options};
+use comparator::Comparator;
+use dataset::{Dataset, Scale};
+use debugger::Breakpoint;
 use eframe::{egui, NativeOptions};
 use egui::Button;
 use egui::{ComboBox, TextEdit};
 use methods::{METHODS, MODIFIERS};
-use sorter::Sorter;
-use std::sync::{LazyLock, Mutex};
+use metrics::{Metrics, Tracker};
+use network::{Diagram, Kind};
+use sorter::{Abort, Counters, Failure, Info, Method, Sorter};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, LazyLock, Mutex};
 use std::time::Duration;
 
+/// How the sort thread is throttled.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Speed {
+    /// Sleep `delay` microseconds per unit of cost before every operation.
+    Delay,
+    /// Run freely, performing `ops_per_frame` operations per displayed frame.
+    PerFrame,
+}
+
+/// Which parts of the window are shown.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    /// Controls, history, code panel and graph.
+    Full,
+    /// Only the graph, e.g. for kiosk displays.
+    Bars,
+    /// Like `Full`, with the comparator network of the method drawn above the
+    /// graph.
+    Network,
+}
+
 struct State {
     paused: bool,
+    speed: Speed,
     delay: u64,
-    panic: Option<String>,
+    ops_per_frame: f64,
+    seed: Option<u64>,
+    #[cfg(feature = "fallible")]
+    fault: fault::Fault,
+    /// Which read or swap `fault` affects, or the range of operations it may panic in.
+    #[cfg(feature = "fallible")]
+    fault_at: u64,
 }
 
+/// Largest array `Generate` accepts; bigger sizes are clamped to it.
+const MAX_DATA_SIZE: u32 = 10_000_000;
+
 static GLOBAL_STATE: LazyLock<Mutex<State>> = LazyLock::new(|| {
     Mutex::new(State {
         paused: false,
+        speed: Speed::Delay,
         delay: 3000,
-        panic: None,
+        ops_per_frame: 1.0,
+        seed: None,
+        #[cfg(feature = "fallible")]
+        fault: fault::Fault::None,
+        #[cfg(feature = "fallible")]
+        fault_at: 1,
     })
 });
 
+/// Attaches to the console of the terminal the program was started from, if
+/// any, so that subcommands, `--help` and errors print there. Being a GUI
+/// program, it has no console of its own.
+#[cfg(windows)]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // Fails harmlessly when started from Explorer.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 fn main() -> Result<(), eframe::Error> {
-    let options = NativeOptions::default();
+    #[cfg(windows)]
+    attach_console();
+    let command = cli::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{}", cli::USAGE);
+        std::process::exit(2);
+    });
+    let options = match command {
+        Command::Run(options) => options,
+        Command::List => {
+            cli::print_list();
+            return Ok(());
+        }
+        Command::Info(name) => {
+            if let Err(e) = cli::print_info(name.as_deref()) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Grade(config) => {
+            GLOBAL_STATE.lock().unwrap().seed = Some(config.seed);
+            match grade::run(&config) {
+                Ok(true) => return Ok(()),
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        Command::States(config, seed) => {
+            GLOBAL_STATE.lock().unwrap().seed = seed;
+            match export::run(&config) {
+                Ok(true) => return Ok(()),
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(2);
+                }
+            }
+        }
+        Command::Torture { size, timeout } => {
+            if !torture::run(size, timeout) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            return Ok(());
+        }
+    };
+
+    let mut global = GLOBAL_STATE.lock().unwrap();
+    global.seed = options.seed;
+    #[cfg(feature = "fallible")]
+    if let Some((fault, at)) = options.fault {
+        global.fault = fault;
+        global.fault_at = at;
+    }
+    if let Some(delay) = options.delay {
+        global.speed = Speed::Delay;
+        global.delay = delay;
+    }
+    if let Some(ops) = options.ops_per_frame {
+        global.speed = Speed::PerFrame;
+        global.ops_per_frame = ops;
+    }
+    drop(global);
+
+    let tracer = match options.trace.as_ref().map(trace::Tracer::open).transpose() {
+        Ok(tracer) => tracer.map(Arc::new),
+        Err(e) => {
+            eprintln!("cannot open trace: {e}");
+            std::process::exit(1);
+        }
+    };
+
     eframe::run_native(
         "Sorting Visualization",
-        options,
-        Box::new(|cc| {
+        NativeOptions::default(),
+        Box::new(move |cc| {
             cc.egui_ctx.set_theme(egui::Theme::Dark);
 
-            Ok(Box::new(SortVis::default()))
+            let mut app = SortVis::new(&options);
+            app.sorter.trace = tracer;
+            if let Some(port) = options.serve {
+                let state = app.sorter.state.clone();
+                app.remote = Some(server::start(port, state, cc.egui_ctx.clone())?);
+            }
+            Ok(Box::new(app))
         }),
     )
 }
 
+/// Restricts the methods offered in the method `ComboBox`.
+#[derive(Default)]
+struct Filter {
+    stable: bool,
+    in_place: bool,
+    adaptive: bool,
+}
+
+impl Filter {
+    const fn matches(&self, info: &Info) -> bool {
+        (!self.stable || info.stable)
+            && (!self.in_place || info.in_place)
+            && (!self.adaptive || info.adaptive)
+    }
+}
+
+/// How a run ended.
+enum Outcome {
+    Sorted,
+    /// Finished, but left the array out of order.
+    Unsorted,
+    /// Finished, but elements were lost or duplicated.
+    Corrupted,
+    Failed,
+    Aborted(Abort),
+}
+
 struct SortResult {
     name: &'static str,
+    /// Name of the file the data was loaded from, if it was not generated.
+    dataset: Option<String>,
     data_size: u32,
-    delay: u64,
+    speed: String,
     time: Duration,
+    outcome: Outcome,
+    /// Operations performed, including those of an aborted run.
+    ops: u64,
+    counters: Counters,
+    /// What the run did to the simulated cache, if one was enabled.
+    cache: Option<cache::Report>,
+    /// Pages the run moved to and from the simulated disk, if it used one.
+    pages: Option<external::Stats>,
+    /// Time taken under the cost model of the run.
+    virtual_time: u64,
+    costs: cost::Costs,
 }
 
+#[allow(clippy::struct_excessive_bools)]
 struct SortVis {
     sorter: Sorter,
     selected_method: usize,
+    /// Method loaded from a script or module, selected rather than
+    /// `selected_method`.
+    loaded: Option<&'static Method>,
+    /// Text of the script editor.
+    script: String,
+    /// Error from the last attempt to load `script`.
+    script_error: Option<String>,
+    script_open: bool,
+    /// Fuel given to WebAssembly modules dropped onto the window.
+    fuel: u64,
+    /// Why the last dropped file could not be loaded, until dismissed.
+    drop_error: Option<String>,
+    /// Values loaded from a file, until data is generated instead.
+    dataset: Option<Dataset>,
+    /// How the values of `dataset` became the array.
+    scale: Scale,
+    /// File the array, or the arrays of a run, are exported to.
+    export_path: String,
+    /// Outcome of the last export.
+    export_status: Option<String>,
+    keys: keys::Bindings,
+    keys_open: bool,
+    /// Query typed into the command palette, while it is open.
+    palette: Option<String>,
+    /// Actions requested through the remote-control server, if serving.
+    remote: Option<Receiver<server::Request>>,
+    filter: Filter,
     data_size_text: String,
     history: Vec<SortResult>,
+    view: View,
+    autostart: bool,
+    exit_when_done: bool,
+    /// Failure shown in the crash dialog until dismissed.
+    crash: Option<Failure>,
+    /// Breakpoint being edited in the debugger, before it is added.
+    new_breakpoint: Breakpoint,
+    /// Sortedness of the array over the current run.
+    metrics: Tracker,
+    /// Layout of the network shown in `View::Network`, kept between frames.
+    diagram: Option<Diagram>,
 }
 
-impl Default for SortVis {
-    fn default() -> Self {
+impl SortVis {
+    fn new(options: &Options) -> Self {
+        let mut data = options.dataset.as_ref().map_or_else(
+            || (1..=options.size.min(MAX_DATA_SIZE)).collect(),
+            |dataset| dataset.data(options.scale),
+        );
+        if let Some(input) = options.input {
+            data = sorter::apply(input, data);
+        }
+
+        let mut sorter = Sorter::new(data);
+        sorter.comparator = options.comparator;
+        sorter.lie_after = options.lie_after;
+        sorter.limits = options.limits;
+        sorter.cache.clone_from(&options.cache);
+        sorter.external = options.external;
+        sorter.costs = options.costs;
+
         Self {
-            sorter: Sorter::new((1..=50).collect::<Vec<u32>>()),
-            selected_method: 0,
+            sorter,
+            selected_method: options.method,
+            loaded: options.loaded,
+            script: options
+                .script
+                .clone()
+                .unwrap_or_else(|| String::from(script::EXAMPLE)),
+            script_error: None,
+            script_open: false,
+            fuel: options.fuel,
+            drop_error: None,
+            dataset: options.dataset.clone(),
+            scale: options.scale,
+            export_path: String::from("array.csv"),
+            export_status: None,
+            keys: keys::Bindings::default(),
+            keys_open: false,
+            palette: None,
+            remote: None,
+            filter: Filter::default(),
             data_size_text: String::new(),
             history: Vec::new(),
+            view: options.view,
+            autostart: options.autostart,
+            exit_when_done: options.exit,
+            crash: None,
+            new_breakpoint: Breakpoint::KINDS[0],
+            metrics: Tracker::default(),
+            diagram: None,
+        }
+    }
+
+    /// The method started by the Start button.
+    fn selected(&self) -> &'static Method {
+        self.loaded.unwrap_or(&METHODS[self.selected_method])
+    }
+
+    /// Records a finished tracked run in the history and picks up failures.
+    fn update_history(&mut self, ctx: &egui::Context) {
+        let mut state = self.sorter.state.lock().unwrap();
+        let failure = state.failure.take();
+        if let Some(stop_time) = state.stop_time.take() {
+            let global = GLOBAL_STATE.lock().unwrap();
+            let elapsed = stop_time.duration_since(state.start_time.unwrap());
+            let result = SortResult {
+                name: self.sorter.method.map_or("", |m| m.name),
+                dataset: self.dataset.as_ref().map(|dataset| dataset.name.clone()),
+                data_size: u32::try_from(state.data.len()).unwrap(),
+                speed: match global.speed {
+                    Speed::Delay => format!("{} μs", global.delay),
+                    Speed::PerFrame => format!("{:.2} op/f", global.ops_per_frame),
+                },
+                time: elapsed,
+                outcome: if failure.is_some() {
+                    Outcome::Failed
+                } else if let Some(abort) = state.aborted.take() {
+                    Outcome::Aborted(abort)
+                } else if !state.is_permutation_of_input() {
+                    Outcome::Corrupted
+                } else if self
+                    .sorter
+                    .comparator
+                    .is_sorted(&state.data, self.sorter.lie_after)
+                {
+                    Outcome::Sorted
+                } else {
+                    Outcome::Unsorted
+                },
+                ops: state.ops,
+                counters: state.counters,
+                cache: state.cache.as_ref().map(|cache| cache.report.clone()),
+                pages: state.disk.as_ref().map(|disk| disk.stats),
+                virtual_time: state.virtual_time(),
+                costs: state.costs,
+            };
+            drop(global);
+            drop(state);
+            let recorded = self
+                .sorter
+                .recorder
+                .as_ref()
+                .and_then(|recorder| recorder.lock().unwrap().take_outcome());
+            if let Some(outcome) = recorded {
+                self.export_status = Some(match outcome {
+                    Ok(()) => format!("saved {}", self.export_path),
+                    Err(err) => err,
+                });
+            }
+            self.history.push(result);
+            if self.history.len() > 4 {
+                self.history.remove(0);
+            }
+            if self.exit_when_done {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+        if failure.is_some() {
+            self.crash = failure;
         }
     }
-}
 
-impl eframe::App for SortVis {
     #[allow(clippy::too_many_lines)]
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.label("Sorting Visualization");
+    fn controls(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.label("Sorting Visualization");
 
-            ui.horizontal(|ui| {
-                // ─── Left: controls ───
-                ui.vertical(|ui| {
-                    // Row 1: Generate
-                    ui.horizontal(|ui| {
-                        let re = ui.add(
-                            TextEdit::singleline(&mut self.data_size_text).desired_width(50.0),
-                        );
-                        let clicked = ui.button("Generate").clicked();
-                        let pressed_enter =
-                            re.lost_focus() && ctx.input(|i| i.key_down(egui::Key::Enter));
-                        if clicked || pressed_enter {
-                            if let Ok(n) = self.data_size_text.parse::<u32>() {
-                                if n > 0 {
-                                    if let Ok(mut s) = self.sorter.state.lock() {
-                                        if !s.sorting {
-                                            s.data = (1..=n).collect();
-                                        }
+        ui.horizontal(|ui| {
+            // ─── Left: controls ───
+            ui.vertical(|ui| {
+                // Row 1: Generate
+                ui.horizontal(|ui| {
+                    let re =
+                        ui.add(TextEdit::singleline(&mut self.data_size_text).desired_width(80.0));
+                    let clicked = ui.button("Generate").clicked();
+                    let pressed_enter =
+                        re.lost_focus() && ctx.input(|i| i.key_down(egui::Key::Enter));
+                    if clicked || pressed_enter {
+                        if let Ok(n) = self.data_size_text.parse::<u32>() {
+                            if n > 0 {
+                                let n = n.min(MAX_DATA_SIZE);
+                                self.data_size_text = n.to_string();
+                                if let Ok(mut s) = self.sorter.state.lock() {
+                                    if !s.sorting {
+                                        s.data = (1..=n).collect();
+                                        self.dataset = None;
                                     }
-                                } else {
-                                    self.data_size_text.clear();
                                 }
                             } else {
                                 self.data_size_text.clear();
                             }
+                        } else {
+                            self.data_size_text.clear();
                         }
-                    });
+                    }
+                    self.dataset_controls(ui);
+                });
 
-                    let sorting_active = self.sorter.is_sorting();
+                let sorting_active = self.sorter.is_sorting();
 
-                    // Row 2: Modifiers
-                    ui.horizontal(|ui| {
-                        for m in MODIFIERS {
-                            if ui
-                                .add_enabled(!sorting_active, Button::new(m.name))
-                                .clicked()
-                            {
-                                self.sorter.method = Some(m.func);
-                                self.sorter.start(false);
-                            }
+                // Row 2: Modifiers
+                ui.horizontal(|ui| {
+                    for m in MODIFIERS {
+                        if ui
+                            .add_enabled(!sorting_active, Button::new(m.name))
+                            .clicked()
+                        {
+                            self.sorter.method = Some(m);
+                            self.sorter.start(false);
                         }
-                    });
+                    }
+                });
 
-                    // Row 3: ComboBox
+                // Row 3: ComboBox
+                ui.horizontal(|ui| {
                     ComboBox::from_label("Select Sorting Method")
-                        .selected_text(METHODS[self.selected_method].name)
+                        .selected_text(self.selected().name)
                         .show_ui(ui, |ui| {
                             for (i, method) in METHODS.iter().enumerate() {
-                                ui.selectable_value(&mut self.selected_method, i, method.name);
+                                if self.filter.matches(&method.info)
+                                    && ui
+                                        .selectable_label(
+                                            self.loaded.is_none() && self.selected_method == i,
+                                            method.name,
+                                        )
+                                        .clicked()
+                                {
+                                    self.selected_method = i;
+                                    self.loaded = None;
+                                }
                             }
                         });
+                    if ui.button("Script…").clicked() {
+                        self.script_open = !self.script_open;
+                    }
+                    if ui.button("Keys…").clicked() {
+                        self.keys_open = !self.keys_open;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.filter.stable, "stable only");
+                    ui.checkbox(&mut self.filter.in_place, "in-place only");
+                    ui.checkbox(&mut self.filter.adaptive, "adaptive only");
+                    let mut network = self.view == View::Network;
+                    if ui.checkbox(&mut network, "network view").changed() {
+                        self.view = if network { View::Network } else { View::Full };
+                    }
+                });
 
-                    // Row 4: Start/Stop, Pause/Resume, Step
+                // Row 4: Start/Stop, Pause/Resume, Step
+                ui.horizontal(|ui| {
+                    let label = if sorting_active { "Stop" } else { "Start" };
+                    if ui.button(label).clicked() {
+                        if sorting_active {
+                            self.sorter.stop();
+                            GLOBAL_STATE.lock().unwrap().paused = false;
+                        } else {
+                            self.sorter.method = Some(self.selected());
+                            self.sorter.start(true);
+                        }
+                    }
+                    let mut global = GLOBAL_STATE.lock().unwrap();
+                    let btn = ui.add_enabled(
+                        sorting_active,
+                        Button::new(if global.paused { "Resume" } else { "Pause" }),
+                    );
+                    if btn.clicked() && sorting_active {
+                        global.paused = !global.paused;
+                        if !global.paused {
+                            self.sorter.resume();
+                        }
+                    }
+                    let paused = global.paused;
+                    drop(global);
+                    if ui
+                        .add_enabled(sorting_active && paused, Button::new("Step back"))
+                        .clicked()
+                    {
+                        self.sorter.step_back();
+                    }
+                    if ui
+                        .add_enabled(sorting_active, Button::new("Step"))
+                        .clicked()
+                    {
+                        self.sorter.step();
+                    }
+                });
+
+                // Row 5: Speed
+                let mut global = GLOBAL_STATE.lock().unwrap();
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut global.speed, Speed::Delay, "Delay (μs)")
+                        .on_hover_text("Per unit of operation cost");
+                    ui.selectable_value(&mut global.speed, Speed::PerFrame, "Ops/frame");
+                    match global.speed {
+                        Speed::Delay => {
+                            let mut d = global.delay;
+                            if ui
+                                .add(egui::Slider::new(&mut d, 0..=100_000).logarithmic(true))
+                                .changed()
+                            {
+                                global.delay = d;
+                            }
+                        }
+                        Speed::PerFrame => {
+                            ui.add(
+                                egui::Slider::new(&mut global.ops_per_frame, 0.01..=10_000_000.0)
+                                    .logarithmic(true),
+                            );
+                        }
+                    }
+                });
+
+                // Row 6: Comparator
+                ui.add_enabled_ui(!sorting_active, |ui| {
                     ui.horizontal(|ui| {
-                        let mut global = GLOBAL_STATE.lock().unwrap();
-                        let label = if sorting_active { "Stop" } else { "Start" };
-                        if ui.button(label).clicked() {
-                            if sorting_active {
-                                self.sorter.stop();
-                                global.paused = false;
-                            } else {
-                                self.sorter.method = Some(METHODS[self.selected_method].func);
-                                self.sorter.start(true);
+                        ComboBox::from_label("Comparator")
+                            .selected_text(self.sorter.comparator.name())
+                            .show_ui(ui, |ui| {
+                                for comparator in Comparator::ALL {
+                                    ui.selectable_value(
+                                        &mut self.sorter.comparator,
+                                        comparator,
+                                        comparator.name(),
+                                    );
+                                }
+                            });
+                        match self.sorter.comparator {
+                            Comparator::LiesAfter => {
+                                ui.add(
+                                    egui::DragValue::new(&mut self.sorter.lie_after)
+                                        .prefix("after "),
+                                );
                             }
+                            Comparator::Keyed => {
+                                ui.add(
+                                    egui::DragValue::new(&mut self.sorter.lie_after)
+                                        .range(1..=u64::MAX)
+                                        .suffix(" values per key"),
+                                );
+                            }
+                            _ => {}
                         }
-                        let btn = ui.add_enabled(
-                            sorting_active,
-                            Button::new(if global.paused { "Resume" } else { "Pause" }),
+                    });
+                });
+
+                // Row 7: Limits
+                ui.add_enabled_ui(!sorting_active, |ui| {
+                    ui.horizontal(|ui| {
+                        let limits = &mut self.sorter.limits;
+                        let mut budget = limits.budget.is_some();
+                        ui.checkbox(&mut budget, "Op budget");
+                        let mut ops = limits.budget.unwrap_or(1_000_000);
+                        ui.add_enabled(budget, egui::DragValue::new(&mut ops).speed(1000.0));
+                        limits.budget = budget.then_some(ops);
+
+                        let mut timeout = limits.timeout.is_some();
+                        ui.checkbox(&mut timeout, "Timeout (s)");
+                        let mut secs = limits.timeout.map_or(60.0, |t| t.as_secs_f64());
+                        ui.add_enabled(
+                            timeout,
+                            egui::DragValue::new(&mut secs)
+                                .range(0.1..=86_400.0)
+                                .speed(0.1),
                         );
-                        if btn.clicked() && sorting_active {
-                            global.paused = !global.paused;
-                            if !global.paused {
-                                self.sorter.resume();
+                        limits.timeout = timeout.then(|| Duration::from_secs_f64(secs));
+                    });
+                });
+
+                // Row 8: Fault injection
+                #[cfg(feature = "fallible")]
+                ui.horizontal(|ui| {
+                    ComboBox::from_label("Fault")
+                        .selected_text(global.fault.name())
+                        .show_ui(ui, |ui| {
+                            for fault in fault::Fault::ALL {
+                                ui.selectable_value(&mut global.fault, fault, fault.name());
                             }
+                        });
+                    let enabled = global.fault != fault::Fault::None;
+                    let n = egui::DragValue::new(&mut global.fault_at)
+                        .range(1..=u64::MAX)
+                        .prefix("n = ");
+                    ui.add_enabled(enabled, n);
+                });
+
+                // Row 9: Cache simulation
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(!sorting_active, |ui| self.cache_controls(ui));
+                    let state = self.sorter.state.lock().unwrap();
+                    if let Some(cache) = &state.cache {
+                        ui.label(format!("≈ {} cycles", cache.report.cycles))
+                            .on_hover_text(cache.report.to_string());
+                    }
+                });
+
+                // Row 10: External memory
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(!sorting_active, |ui| self.external_controls(ui));
+                    let state = self.sorter.state.lock().unwrap();
+                    if let Some(disk) = &state.disk {
+                        ui.label(disk.stats.to_string());
+                    }
+                });
+
+                // Row 11: Cost model
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(!sorting_active, |ui| self.cost_controls(ui));
+                    let time = self.sorter.state.lock().unwrap().virtual_time();
+                    ui.label(format!("virtual time {time}"));
+                });
+
+                // Row 12: Export
+                ui.horizontal(|ui| self.export_controls(ui, sorting_active));
+            });
+
+            // Spacer to push the table right
+            let cell_width = 75.0;
+            let spacing = 10.0;
+            let table_width = 10.0f32.mul_add(cell_width, 10.0 * spacing);
+            let avail = ui.available_width();
+            if avail > table_width {
+                ui.add_space(avail - table_width);
+            }
+
+            // Right: 5×5 table
+            ui.vertical(|ui| {
+                egui::Grid::new("value_table")
+                    .striped(true)
+                    .spacing(egui::vec2(spacing, 4.0))
+                    .min_col_width(cell_width)
+                    .show(ui, |ui| {
+                        ui.label("Sort");
+                        ui.label("Data");
+                        ui.label("Size");
+                        ui.label("Speed");
+                        ui.label("Time (ms)");
+                        ui.label("Virtual");
+                        ui.label("Ops");
+                        ui.label("Cycles");
+                        ui.label("Pages");
+                        ui.label("Result");
+                        ui.end_row();
+                        for row in self.history.iter().rev() {
+                            ui.label(row.name);
+                            ui.label(row.dataset.as_deref().unwrap_or("generated"));
+                            ui.label(row.data_size.to_string());
+                            ui.label(&row.speed);
+                            let time_us = row.time.as_millis();
+                            if time_us == 0 {
+                                ui.label(format!("{:.6}", row.time.as_secs_f64() * 1_000.0));
+                            } else {
+                                ui.label(time_us.to_string());
+                            }
+                            let c = row.costs;
+                            ui.label(row.virtual_time.to_string())
+                                .on_hover_text(format!(
+                                    "{}: read {}, compare {}, swap {}, page {}",
+                                    c.name().unwrap_or("custom"),
+                                    c.read,
+                                    c.compare,
+                                    c.swap,
+                                    c.page
+                                ));
+                            ui.label(row.ops.to_string()).on_hover_text(format!(
+                                "reads: {}\ncompares: {}\nswaps: {}\nwrites: {}",
+                                row.counters.reads,
+                                row.counters.compares,
+                                row.counters.swaps,
+                                row.counters.writes
+                            ));
+                            match &row.cache {
+                                Some(report) => {
+                                    ui.label(report.cycles.to_string())
+                                        .on_hover_text(report.to_string());
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+                            match row.pages {
+                                Some(stats) => {
+                                    ui.label((stats.reads + stats.writes).to_string())
+                                        .on_hover_text(stats.to_string());
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+                            ui.label(match row.outcome {
+                                Outcome::Sorted => String::from("sorted"),
+                                Outcome::Unsorted => String::from("unsorted"),
+                                Outcome::Corrupted => String::from("corrupted"),
+                                Outcome::Failed => String::from("failed"),
+                                Outcome::Aborted(abort) => format!("aborted: {abort}"),
+                            });
+                            ui.end_row();
                         }
+                    });
+            });
+        });
+    }
+
+    /// Edits the cache hierarchy simulated by the next run.
+    fn cache_controls(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.sorter.cache.is_some();
+        ui.checkbox(&mut enabled, "Cache");
+        if enabled != self.sorter.cache.is_some() {
+            self.sorter.cache = enabled.then(cache::Config::default);
+        }
+        let Some(config) = &mut self.sorter.cache else {
+            return;
+        };
+
+        ui.add(
+            egui::DragValue::new(&mut config.line)
+                .range(4..=4096)
+                .prefix("line ")
+                .suffix(" B"),
+        );
+        for (k, level) in config.levels.iter_mut().enumerate() {
+            let mut kib = level.size >> 10;
+            ui.label(format!("L{}", k + 1))
+                .on_hover_text(format!("{} cycles per hit", level.latency));
+            ui.add(
+                egui::DragValue::new(&mut kib)
+                    .range(1..=1 << 20)
+                    .suffix(" KiB"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut level.ways)
+                    .range(1..=64)
+                    .suffix("-way"),
+            );
+            level.size = kib << 10;
+        }
+        if config.levels.len() > 1 && ui.small_button("−").clicked() {
+            config.levels.pop();
+        }
+        if config.levels.len() < 4 && ui.small_button("+").clicked() {
+            let last = config.levels.last().copied();
+            config.levels.push(cache::Level {
+                size: last.map_or(32 << 10, |level| level.size * 8),
+                ways: last.map_or(8, |level| level.ways),
+                latency: cache::Config::latency(config.levels.len()),
+            });
+        }
+    }
+
+    /// Edits the memory and page size of the disk simulated by the next run.
+    fn external_controls(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.sorter.external.is_some();
+        ui.checkbox(&mut enabled, "External memory");
+        if enabled != self.sorter.external.is_some() {
+            self.sorter.external = enabled.then(external::Config::default);
+        }
+        let Some(config) = &mut self.sorter.external else {
+            return;
+        };
+
+        ui.add(
+            egui::DragValue::new(&mut config.frames)
+                .range(external::Config::MIN_FRAMES..=1 << 16)
+                .suffix(" pages of"),
+        );
+        ui.add(
+            egui::DragValue::new(&mut config.page)
+                .range(1..=1 << 16)
+                .suffix(" elements"),
+        );
+    }
+
+    /// Exports the array now, or the arrays of the next run once it ends.
+    fn export_controls(&mut self, ui: &mut egui::Ui, sorting: bool) {
+        ui.add(TextEdit::singleline(&mut self.export_path).desired_width(120.0));
+        if ui
+            .button("Export")
+            .on_hover_text("Write the array to the file, as CSV or .json")
+            .clicked()
+        {
+            let data = self.sorter.state.lock().unwrap().data.clone();
+            self.export(&export::array(&data, export::Format::of(&self.export_path)));
+        }
+        ui.add_enabled_ui(!sorting, |ui| {
+            let current = self.sorter.recorder.as_ref().map(|recorder| {
+                let recorder = recorder.lock().unwrap();
+                (recorder.every(), recorder.path().map(str::to_owned))
+            });
+            let mut enabled = current.is_some();
+            ui.checkbox(&mut enabled, "Every")
+                .on_hover_text("Write the array every K operations of the next runs");
+            let mut every = current.as_ref().map_or(100, |&(every, _)| every);
+            ui.add_enabled(
+                enabled,
+                egui::DragValue::new(&mut every)
+                    .range(1..=u64::MAX)
+                    .suffix(" ops"),
+            );
+            // Runs write to the file as they go, so the recorder follows the path.
+            let wanted = enabled.then(|| (every, Some(self.export_path.clone())));
+            if !sorting && wanted != current {
+                self.sorter.recorder = wanted.map(|(every, path)| {
+                    let format = export::Format::of(&self.export_path);
+                    Arc::new(Mutex::new(export::Recorder::new(path, format, every)))
+                });
+            }
+        });
+        if let Some(status) = &self.export_status {
+            ui.label(status);
+        }
+    }
+
+    /// Writes `contents` to the export file, noting how it went.
+    fn export(&mut self, contents: &str) {
+        self.export_status = Some(match export::save(&self.export_path, contents) {
+            Ok(()) => format!("saved {}", self.export_path),
+            Err(err) => err,
+        });
+    }
+
+    /// Edits the cost of each kind of operation for the next run.
+    fn cost_controls(&mut self, ui: &mut egui::Ui) {
+        let costs = &mut self.sorter.costs;
+        ComboBox::from_label("Costs")
+            .selected_text(costs.name().unwrap_or("custom"))
+            .show_ui(ui, |ui| {
+                for (name, preset) in cost::Costs::PRESETS {
+                    ui.selectable_value(costs, preset, name);
+                }
+            });
+        for (label, cost) in [
+            ("read ", &mut costs.read),
+            ("compare ", &mut costs.compare),
+            ("swap ", &mut costs.swap),
+            ("write ", &mut costs.write),
+            ("page ", &mut costs.page),
+        ] {
+            ui.add(egui::DragValue::new(cost).prefix(label));
+        }
+    }
+
+    /// Draws the array and scratch files of the simulated disk with the pages
+    /// in memory highlighted.
+    fn disk_panel(&self, ui: &mut egui::Ui) {
+        let state = self.sorter.state.lock().unwrap();
+        let Some(disk) = &state.disk else {
+            return;
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let height = 60.0 * (1 + disk.files.len()) as f32;
+        let (_, area) = ui.allocate_space(egui::vec2(ui.available_width(), height));
+        disk.draw(&ui.painter_at(area), area, &state.data);
+    }
+
+    /// Samples the sortedness metrics of the array and charts them over the run.
+    fn metrics_panel(&mut self, ui: &mut egui::Ui) {
+        let state = self.sorter.state.lock().unwrap();
+        let sample = self
+            .metrics
+            .due(state.start_time, state.ops, !state.sorting)
+            .then(|| (state.ops, state.data.clone()));
+        drop(state);
+        if let Some((ops, data)) = sample {
+            self.metrics.sample(ops, &data);
+        }
+        let Some(&(ops, metrics)) = self.metrics.samples.last() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            let (_, area) = ui.allocate_space(egui::vec2(300.0, 80.0));
+            metrics::draw_chart(&ui.painter_at(area), area, &self.metrics.samples);
+
+            let values = [
+                metrics.inversions.to_string(),
+                metrics.runs.to_string(),
+                metrics.lis.to_string(),
+                metrics.footrule.to_string(),
+                metrics.in_place.to_string(),
+            ];
+            egui::Grid::new("metrics").show(ui, |ui| {
+                for ((name, color), value) in Metrics::SERIES.iter().zip(values) {
+                    ui.colored_label(*color, *name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+                ui.label("Kendall tau");
+                ui.label(format!("{:.3}", metrics.kendall_tau()));
+                ui.end_row();
+                ui.label("after ops");
+                ui.label(ops.to_string());
+                ui.end_row();
+            });
+        });
+    }
+
+    /// Draws the comparator network of the running method, or of the selected one
+    /// when idle, with the executing layer highlighted.
+    fn network_panel(&mut self, ui: &mut egui::Ui) {
+        let (_, area) = ui.allocate_space(egui::vec2(
+            ui.available_width(),
+            ui.available_height() / 2.0,
+        ));
+        let painter = ui.painter_at(area);
+        let note = |text: String| {
+            painter.text(
+                area.center(),
+                egui::Align2::CENTER_CENTER,
+                text,
+                egui::FontId::proportional(16.0),
+                egui::Color32::GRAY,
+            );
+        };
+
+        let state = self.sorter.state.lock().unwrap();
+        let method = match self.sorter.method {
+            Some(method) if state.sorting => method,
+            _ => self.selected(),
+        };
+        let Some(kind) = Kind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == method.name)
+        else {
+            note(format!("{} is not a sorting network", method.name));
+            return;
+        };
+        let wires = state.data.len();
+        if wires > network::MAX_WIRES {
+            note(format!(
+                "The network view is limited to {} elements",
+                network::MAX_WIRES
+            ));
+            return;
+        }
+
+        let diagram = match self.diagram.take() {
+            Some(diagram) if diagram.kind == kind && diagram.wires == wires => diagram,
+            _ => Diagram::new(kind, wires),
+        };
+        let layer = state.layer.filter(|_| state.sorting);
+        diagram.draw(&painter, area, layer, &state.cursors);
+        drop(state);
+        self.diagram = Some(diagram);
+    }
+
+    /// Shows the metadata and code of the running method, or of the selected one
+    /// when idle, with the line of the last operation highlighted.
+    fn code_panel(&mut self, ctx: &egui::Context) {
+        let state = self.sorter.state.lock().unwrap();
+        let (method, line) = match self.sorter.method {
+            Some(method) if state.sorting => (method, state.line),
+            _ => (self.selected(), None),
+        };
+        drop(state);
+
+        egui::SidePanel::right("code_panel").show(ctx, |ui| {
+            ui.heading(method.name);
+            ui.label(method.info.description);
+            egui::Grid::new("info_table").show(ui, |ui| {
+                let yes_no = |b: bool| if b { "yes" } else { "no" };
+                for (key, value) in [
+                    ("Best", method.info.best),
+                    ("Average", method.info.average),
+                    ("Worst", method.info.worst),
+                    ("Memory", method.info.memory),
+                    ("Stable", yes_no(method.info.stable)),
+                    ("In-place", yes_no(method.info.in_place)),
+                    ("Adaptive", yes_no(method.info.adaptive)),
+                ] {
+                    ui.label(key);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+            for reference in method.info.references {
+                if reference.starts_with("http") {
+                    ui.hyperlink(reference);
+                } else {
+                    ui.small(*reference);
+                }
+            }
+            ui.separator();
+            let loaded = match method.name {
+                name if name == script::METHOD.name => script::lines(),
+                name if name == wasm::METHOD.name => wasm::lines(),
+                _ => Vec::new(),
+            };
+            let code = loaded.iter().map(String::as_str);
+            for (i, text) in code.chain(method.code.iter().copied()).enumerate() {
+                let mut text = egui::RichText::new(format!("{:>2}  {text}", i + 1)).monospace();
+                if line == Some(i + 1) {
+                    text = text
+                        .background_color(egui::Color32::from_rgb(90, 70, 20))
+                        .color(egui::Color32::WHITE);
+                }
+                ui.label(text);
+            }
+            self.debugger(ui);
+        });
+    }
+
+    /// Carries out the actions requested by remote clients since the last frame.
+    fn serve_remote(&mut self) {
+        let Some(remote) = &self.remote else {
+            return;
+        };
+        for request in remote.try_iter().collect::<Vec<_>>() {
+            let result = self.perform(request.action);
+            // The client may have given up waiting.
+            let _ = request.reply.send(result);
+        }
+    }
+
+    /// Does what the control bound to `action` would, if it is enabled.
+    fn trigger(&mut self, action: keys::Action) {
+        let sorting = self.sorter.is_sorting();
+        let paused = GLOBAL_STATE.lock().unwrap().paused;
+        // Refused actions are those whose controls are disabled.
+        let _ = match action {
+            keys::Action::StartStop if sorting => self.perform(server::Action::Stop),
+            keys::Action::StartStop => self.perform(server::Action::Start(None)),
+            keys::Action::PauseResume if paused => self.perform(server::Action::Resume),
+            keys::Action::PauseResume => self.perform(server::Action::Pause),
+            keys::Action::Step => self.perform(server::Action::Step),
+            keys::Action::StepBack => {
+                if sorting && paused {
+                    self.sorter.step_back();
+                }
+                Ok(())
+            }
+            keys::Action::SpeedUp | keys::Action::SpeedDown => {
+                let faster = action == keys::Action::SpeedUp;
+                let mut global = GLOBAL_STATE.lock().unwrap();
+                match global.speed {
+                    Speed::Delay if faster => global.delay /= 2,
+                    Speed::Delay => global.delay = global.delay.saturating_mul(2).clamp(1, 100_000),
+                    Speed::PerFrame => {
+                        let factor = if faster { 2.0 } else { 0.5 };
+                        global.ops_per_frame =
+                            (global.ops_per_frame * factor).clamp(0.01, 10_000_000.0);
+                    }
+                }
+                Ok(())
+            }
+            keys::Action::NextMethod | keys::Action::PreviousMethod => {
+                let step = if action == keys::Action::NextMethod {
+                    1
+                } else {
+                    METHODS.len() - 1
+                };
+                self.selected_method = (self.selected_method + step) % METHODS.len();
+                self.loaded = None;
+                Ok(())
+            }
+            keys::Action::Modifier(i) => self.perform(server::Action::Modify(&MODIFIERS[i])),
+            keys::Action::Generate => {
+                let len = self.sorter.state.lock().unwrap().data.len();
+                let n = self
+                    .data_size_text
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .unwrap_or_else(|| u32::try_from(len).unwrap().max(1));
+                self.perform(server::Action::Generate(n))
+            }
+            keys::Action::Palette => {
+                self.palette = Some(String::new());
+                Ok(())
+            }
+            keys::Action::Settings => {
+                self.keys_open = !self.keys_open;
+                Ok(())
+            }
+        };
+    }
+
+    /// Lists every action with its key, rebinding the one clicked to the next
+    /// key pressed.
+    fn key_settings(&mut self, ctx: &egui::Context) {
+        let mut open = self.keys_open;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::Grid::new("keys").striped(true).show(ui, |ui| {
+                    for (action, key) in self.keys.keys.clone() {
+                        ui.label(action.label());
+                        let text = if self.keys.capturing == Some(action) {
+                            String::from("press a key…")
+                        } else {
+                            key.map_or_else(|| String::from("-"), |key| ctx.format_shortcut(&key))
+                        };
                         if ui
-                            .add_enabled(sorting_active, Button::new("Step"))
+                            .button(text)
+                            .on_hover_text("Click, then press the new key or Escape")
                             .clicked()
                         {
-                            self.sorter.resume();
+                            self.keys.capturing = Some(action);
                         }
-                    });
-
-                    // Row 5: Delay slider
-                    let mut global = GLOBAL_STATE.lock().unwrap();
-                    ui.horizontal(|ui| {
-                        ui.label("Delay (μs)");
-                        let mut d = global.delay;
                         if ui
-                            .add(egui::Slider::new(&mut d, 0..=100_000).logarithmic(true))
-                            .changed()
+                            .add_enabled(key.is_some(), Button::new("Clear"))
+                            .clicked()
                         {
-                            global.delay = d;
+                            self.keys.bind(action, None);
                         }
-                    });
+                        ui.end_row();
+                    }
                 });
+                if ui.button("Restore defaults").clicked() {
+                    self.keys = keys::Bindings::default();
+                }
+            });
+        self.keys_open = open;
+        if !open {
+            self.keys.capturing = None;
+        }
+    }
+
+    /// Runs the action or selects the method picked from a filtered list.
+    fn command_palette(&mut self, ctx: &egui::Context) {
+        let Some(mut query) = self.palette.take() else {
+            return;
+        };
+        let needle = query.to_lowercase();
+        let entries = keys::Action::all()
+            .filter(|&action| action != keys::Action::Palette)
+            .map(|action| {
+                let key = self.keys.key(action).map(|key| ctx.format_shortcut(&key));
+                (action.label(), key, Some(action), None)
+            })
+            .chain(
+                METHODS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| (format!("Method: {}", m.name), None, None, Some(i))),
+            )
+            .filter(|(label, ..)| label.to_lowercase().contains(&needle))
+            .collect::<Vec<_>>();
 
-                // Spacer to push the table right
-                let cell_width = 75.0;
-                let spacing = 10.0;
-                let table_width = 4.0f32.mul_add(cell_width, 4.0 * spacing);
-                let avail = ui.available_width();
-                if avail > table_width {
-                    ui.add_space(avail - table_width);
+        let mut chosen = None;
+        let mut close = ctx.input(|i| i.key_pressed(egui::Key::Escape));
+        egui::Window::new("Command palette")
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let edit = ui.add(TextEdit::singleline(&mut query).hint_text("Type a command…"));
+                edit.request_focus();
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    chosen = entries
+                        .first()
+                        .map(|&(_, _, action, method)| (action, method));
                 }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (label, key, action, method) in &entries {
+                            ui.horizontal(|ui| {
+                                if ui.selectable_label(false, label).clicked() {
+                                    chosen = Some((*action, *method));
+                                }
+                                if let Some(key) = key {
+                                    ui.weak(key);
+                                }
+                            });
+                        }
+                    });
+            });
+        match chosen {
+            Some((Some(action), _)) => {
+                close = true;
+                self.trigger(action);
+            }
+            Some((None, Some(method))) => {
+                close = true;
+                self.selected_method = method;
+                self.loaded = None;
+            }
+            _ => {}
+        }
+        if !close {
+            self.palette = Some(query);
+        }
+    }
 
-                // Update history
-                let mut state = self.sorter.state.lock().unwrap();
-                if let Some(stop_time) = state.stop_time.take() {
-                    let global = GLOBAL_STATE.lock().unwrap();
-                    let elapsed = stop_time.duration_since(state.start_time.unwrap());
-                    let result = SortResult {
-                        name: METHODS[self.selected_method].name,
-                        data_size: u32::try_from(state.data.len()).unwrap(),
-                        delay: global.delay,
-                        time: elapsed,
-                    };
-                    drop(global);
-                    self.history.push(result);
-                    if self.history.len() > 4 {
-                        self.history.remove(0);
+    fn perform(&mut self, action: server::Action) -> Result<(), String> {
+        let sorting = self.sorter.is_sorting();
+        match action {
+            server::Action::Generate(_) | server::Action::Modify(_) | server::Action::Start(_)
+                if sorting =>
+            {
+                return Err(String::from("a sort is running"));
+            }
+            server::Action::Pause | server::Action::Resume | server::Action::Step if !sorting => {
+                return Err(String::from("no sort is running"));
+            }
+            server::Action::Generate(n) => {
+                let n = n.min(MAX_DATA_SIZE);
+                self.data_size_text = n.to_string();
+                self.sorter.state.lock().unwrap().data = (1..=n).collect();
+                self.dataset = None;
+            }
+            server::Action::Modify(modifier) => {
+                self.sorter.method = Some(modifier);
+                self.sorter.start(false);
+            }
+            server::Action::Start(method) => {
+                if let Some(method) = method {
+                    self.selected_method =
+                        METHODS.iter().position(|m| m.name == method.name).unwrap();
+                    self.loaded = None;
+                }
+                self.sorter.method = Some(self.selected());
+                self.sorter.start(true);
+            }
+            server::Action::Stop => {
+                self.sorter.stop();
+                GLOBAL_STATE.lock().unwrap().paused = false;
+            }
+            server::Action::Pause => GLOBAL_STATE.lock().unwrap().paused = true,
+            server::Action::Resume => {
+                GLOBAL_STATE.lock().unwrap().paused = false;
+                self.sorter.resume();
+            }
+            server::Action::Step => self.sorter.step(),
+        }
+        Ok(())
+    }
+
+    /// Shows the name of the loaded dataset and how its values are scaled.
+    fn dataset_controls(&mut self, ui: &mut egui::Ui) {
+        let Some(dataset) = &self.dataset else {
+            return;
+        };
+        ui.label(&dataset.name);
+        let scale = self.scale;
+        ComboBox::from_id_salt("scale")
+            .selected_text(self.scale.name())
+            .show_ui(ui, |ui| {
+                for option in Scale::ALL {
+                    ui.selectable_value(&mut self.scale, option, option.name());
+                }
+            });
+        if self.scale != scale {
+            let mut state = self.sorter.state.lock().unwrap();
+            if state.sorting {
+                self.scale = scale;
+            } else {
+                state.data = dataset.data(self.scale);
+            }
+        }
+    }
+
+    /// Loads WebAssembly modules dropped onto the window as the selected method,
+    /// and any other file as the data to sort.
+    fn load_dropped(&mut self, ctx: &egui::Context) {
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            let name = file
+                .path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map_or_else(
+                    || file.name.clone(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+            let extension = name
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_ascii_lowercase());
+            let bytes = match (&file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path).map_err(|err| err.to_string()),
+                (None, None) => Err(String::from("no contents")),
+            };
+            let loaded = bytes.and_then(|bytes| {
+                if let Some("wasm" | "wat") = extension.as_deref() {
+                    wasm::load(&name, &bytes, self.fuel)?;
+                    self.loaded = Some(&wasm::METHOD);
+                    Ok(())
+                } else {
+                    let dataset = Dataset::parse(&name, &bytes, None)?;
+                    let mut state = self.sorter.state.lock().unwrap();
+                    if state.sorting {
+                        return Err(String::from("a sort is running"));
                     }
+                    state.data = dataset.data(self.scale);
+                    self.data_size_text = state.data.len().to_string();
+                    drop(state);
+                    self.dataset = Some(dataset);
+                    Ok(())
                 }
-                drop(state);
-
-                // Right: 5×5 table
-                ui.vertical(|ui| {
-                    egui::Grid::new("value_table")
-                        .striped(true)
-                        .spacing(egui::vec2(spacing, 4.0))
-                        .min_col_width(cell_width)
-                        .show(ui, |ui| {
-                            ui.label("Sort");
-                            ui.label("Size");
-                            ui.label("Delay (μs)");
-                            ui.label("Time (ms)");
-                            ui.end_row();
-                            for row in self.history.iter().rev() {
-                                ui.label(row.name);
-                                ui.label(row.data_size.to_string());
-                                ui.label(row.delay.to_string());
-                                let time_us = row.time.as_millis();
-                                if time_us == 0 {
-                                    ui.label(format!("{:.6}", row.time.as_secs_f64() * 1_000.0));
-                                } else {
-                                    ui.label(time_us.to_string());
+            });
+            if let Err(err) = loaded {
+                self.drop_error = Some(format!("{name}: {err}"));
+            }
+        }
+    }
+
+    /// Edits the script and loads it as the selected method.
+    fn script_editor(&mut self, ctx: &egui::Context) {
+        let sorting = self.sorter.is_sorting();
+        let mut open = self.script_open;
+        egui::Window::new("Script")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let load = ui.add_enabled(!sorting, Button::new("Load"));
+                    let run = ui.add_enabled(!sorting, Button::new("Run"));
+                    if ui.button("Example").clicked() {
+                        self.script = String::from(script::EXAMPLE);
+                    }
+                    if load.clicked() || run.clicked() {
+                        match script::load(&self.script) {
+                            Ok(()) => {
+                                self.script_error = None;
+                                self.loaded = Some(&script::METHOD);
+                                if run.clicked() {
+                                    self.sorter.method = Some(&script::METHOD);
+                                    self.sorter.start(true);
                                 }
-                                ui.end_row();
                             }
-                        });
+                            Err(err) => self.script_error = Some(err.to_string()),
+                        }
+                    }
+                });
+                if let Some(error) = &self.script_error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add(
+                        TextEdit::multiline(&mut self.script)
+                            .code_editor()
+                            .desired_rows(20)
+                            .desired_width(f32::INFINITY),
+                    );
                 });
             });
+        self.script_open = open;
+    }
+
+    /// Lists the breakpoints and lets the user add or remove them.
+    fn debugger(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.strong("Breakpoints");
+        let paused = GLOBAL_STATE.lock().unwrap().paused;
+        let state = self.sorter.state.lock().unwrap();
+        let (phase, hit, sorting) = (state.phase.clone(), state.hit, state.sorting);
+        let mut breakpoints = state.breakpoints.clone();
+        drop(state);
+
+        if let Some(phase) = phase {
+            ui.label(format!("Phase: {phase}"));
+        }
+        if let Some(hit) = hit.filter(|_| sorting && paused) {
+            ui.colored_label(egui::Color32::YELLOW, format!("Paused at {hit}"));
+        }
+
+        let mut changed = false;
+        let mut removed = None;
+        for (i, breakpoint) in breakpoints.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").clicked() {
+                    removed = Some(i);
+                }
+                ui.label(breakpoint.to_string());
+            });
+        }
+        if let Some(i) = removed {
+            breakpoints.remove(i);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("breakpoint_kind")
+                .selected_text(self.new_breakpoint.kind())
+                .show_ui(ui, |ui| {
+                    for kind in Breakpoint::KINDS {
+                        let selected = self.new_breakpoint.kind() == kind.kind();
+                        if ui.selectable_label(selected, kind.kind()).clicked() {
+                            self.new_breakpoint = kind;
+                        }
+                    }
+                });
+            match &mut self.new_breakpoint {
+                Breakpoint::Swap(i) => {
+                    ui.add(egui::DragValue::new(i));
+                }
+                Breakpoint::Value { value, position } => {
+                    ui.add(egui::DragValue::new(value).prefix("value "));
+                    ui.add(egui::DragValue::new(position).prefix("at "));
+                }
+                Breakpoint::Compares(n) => {
+                    ui.add(egui::DragValue::new(n));
+                }
+                Breakpoint::Phase => {}
+            }
+            if ui.button("Add").clicked() && !breakpoints.contains(&self.new_breakpoint) {
+                breakpoints.push(self.new_breakpoint);
+                changed = true;
+            }
+        });
+        if changed {
+            self.sorter.state.lock().unwrap().breakpoints = breakpoints;
+        }
+    }
+}
+
+impl eframe::App for SortVis {
+    #[allow(clippy::too_many_lines)]
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if std::mem::take(&mut self.autostart) {
+            self.sorter.method = Some(self.selected());
+            self.sorter.start(true);
+        }
+        self.update_history(ctx);
+        let global = GLOBAL_STATE.lock().unwrap();
+        if global.speed == Speed::PerFrame && !global.paused {
+            self.sorter.advance_frame(global.ops_per_frame);
+        }
+        drop(global);
+        if self.view != View::Bars {
+            self.code_panel(ctx);
+            self.script_editor(ctx);
+        }
+        self.load_dropped(ctx);
+        self.serve_remote();
+        for action in self.keys.pressed(ctx) {
+            self.trigger(action);
+        }
+        self.key_settings(ctx);
+        self.command_palette(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.view != View::Bars {
+                self.controls(ctx, ui);
+                self.metrics_panel(ui);
+            }
+            if self.view == View::Network {
+                ui.add_space(20.0);
+                self.network_panel(ui);
+            }
+            if self.view != View::Bars {
+                self.disk_panel(ui);
+            }
 
             // Graph
             let state = self.sorter.state.lock().unwrap();
             ui.add_space(20.0);
             let (_, graph_area) =
                 ui.allocate_space(egui::vec2(ui.available_width(), ui.available_height()));
-            #[allow(clippy::cast_precision_loss)]
-            let maxv = *state.data.iter().max().unwrap_or(&1) as f32;
-            let painter = ui.painter_at(graph_area);
-            #[allow(clippy::cast_precision_loss)]
-            let bar_w = graph_area.size().x / state.data.len().max(1) as f32;
-
-            #[allow(clippy::cast_precision_loss)]
-            for (i, &v) in state.data.iter().enumerate() {
-                let h = graph_area.size().y * (v as f32 / maxv);
-                let rect = egui::Rect::from_min_size(
-                    egui::pos2(
-                        (i as f32).mul_add(bar_w, graph_area.min.x),
-                        graph_area.max.y - h,
-                    ),
-                    egui::vec2((bar_w - 2.0).max(bar_w * 0.9), h),
-                );
-                let color = match state.step {
-                    Some(sorter::Step::Read(j)) if j == i => egui::Color32::GREEN,
-                    Some(sorter::Step::Swap(j, k)) if j == i || k == i => egui::Color32::RED,
-                    _ => egui::Color32::LIGHT_BLUE,
-                };
-                painter.rect_filled(rect, 0.0, color);
-            }
+            graph::draw_bars(
+                &ui.painter_at(graph_area),
+                graph_area,
+                &state.data,
+                &state.cursors,
+            );
 
             if state.sorting && !GLOBAL_STATE.lock().unwrap().paused {
                 ctx.request_repaint();
@@ -258,15 +1516,17 @@ impl eframe::App for SortVis {
             drop(state);
 
             // Show crash dialog if present
-            let mut global = GLOBAL_STATE.lock().unwrap();
             let mut dismissed = false;
-            if let Some(crash_msg) = &global.panic {
+            if let Some(failure) = &self.crash {
                 egui::Window::new("Crash")
                     .collapsible(false)
                     .resizable(false)
                     .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                     .show(ctx, |ui| {
-                        ui.label(egui::RichText::new(crash_msg).size(24.0).strong());
+                        ui.label(egui::RichText::new(failure.to_string()).size(24.0).strong());
+                        ui.label(format!("after {} operations", failure.operation));
+                        let (_, area) = ui.allocate_space(egui::vec2(400.0, 120.0));
+                        graph::draw_bars(&ui.painter_at(area), area, &failure.data, &[]);
                         if ui.button("OK").clicked() {
                             dismissed = true;
                         }
@@ -274,8 +1534,22 @@ impl eframe::App for SortVis {
             }
 
             if dismissed {
-                self.sorter.stop();
-                global.panic = None;
+                self.crash = None;
+            }
+
+            if let Some(error) = &self.drop_error {
+                let mut dismissed = false;
+                egui::Window::new("Cannot load file")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        ui.label(error);
+                        dismissed = ui.button("OK").clicked();
+                    });
+                if dismissed {
+                    self.drop_error = None;
+                }
             }
         });
     }
@@ -1,16 +1,259 @@
-use crate::sorter::{Interface, Method};
-use rand::Rng;
+use crate::external::Config;
+use crate::network::Kind;
+use crate::sorter::{Info, Interface, Lockstep, Method};
+use crate::GLOBAL_STATE;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Recursion depth up to which the parallel methods fork, giving them up to
+/// `2^THREAD_DEPTH` cursors.
+const THREAD_DEPTH: u32 = 2;
+/// Threads sharing a layer of independent compare-exchanges.
+const THREADS: usize = 4;
+
+/// Runs layers of independent operations on `THREADS` cursors kept for the
+/// whole run. Before each layer `d` the first cursor calls `next(int, d)` for
+/// the layer and its number of operations, or `None` once done; the cursors
+/// then call `f(int, &layer, i)` for contiguous chunks of the operations.
+fn for_each_layer<L, N, F>(int: &Interface, next: N, f: F)
+where
+    L: Send + Sync,
+    N: FnMut(&Interface, usize) -> Option<(L, usize)> + Send,
+    F: Fn(&Interface, &L, usize) + Sync,
+{
+    let next = Mutex::new(next);
+    let layer = RwLock::new(None);
+    let lockstep = Lockstep::new(THREADS);
+    int.parallel(THREADS, |t, int| {
+        for d in 0.. {
+            if t == 0 {
+                *layer.write().unwrap() = next.lock().unwrap()(int, d);
+            }
+            lockstep.wait(int);
+            let guard = layer.read().unwrap();
+            let Some((layer, n)) = &*guard else { break };
+            for i in t * n / THREADS..(t + 1) * n / THREADS {
+                f(int, layer, i);
+            }
+            drop(guard);
+            lockstep.wait(int);
+        }
+    });
+}
+
+/// Runs the sorting network `kind` layer by layer, splitting each layer among
+/// `THREADS` cursors.
+fn run_network(int: &Interface, kind: Kind) {
+    let len = int.len();
+    let depth = kind.depth(len);
+    for_each_layer(
+        int,
+        |int, d| {
+            (d < depth).then(|| {
+                int.layer(d);
+                let gates = kind.layer(len, d);
+                let n = gates.len();
+                (gates, n)
+            })
+        },
+        |int, gates, g| {
+            let (i, j) = gates[g];
+            int.line(3);
+            if int.compare(i, j).is_gt() {
+                int.swap(i, j);
+            }
+        },
+    );
+}
+
+/// First index in `lo..hi` for which `pred` is false, assuming it holds for a
+/// prefix of the range.
+fn partition_point(mut lo: usize, mut hi: usize, pred: impl Fn(usize) -> bool) -> usize {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Reverses `lo..hi` with swaps.
+fn reverse(int: &Interface, mut lo: usize, mut hi: usize) {
+    while lo + 1 < hi {
+        int.swap(lo, hi - 1);
+        lo += 1;
+        hi -= 1;
+    }
+}
+
+/// Whether `sift_down` marks the lines of the `heap` method's code.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Lines {
+    Marked,
+    Unmarked,
+}
+
+/// Sifts the element at `root` of the max-heap in `lo..lo + end` down to its
+/// place.
+fn sift_down(int: &Interface, lo: usize, mut root: usize, end: usize, lines: Lines) {
+    let line = |n| {
+        if lines == Lines::Marked {
+            int.line(n);
+        }
+    };
+    while 2 * root + 1 < end {
+        let mut child = 2 * root + 1;
+        line(8);
+        if child + 1 < end && int.compare(lo + child, lo + child + 1).is_lt() {
+            child += 1;
+        }
+        line(9);
+        if int.compare(lo + root, lo + child).is_ge() {
+            return;
+        }
+        line(10);
+        int.swap(lo + root, lo + child);
+        root = child;
+    }
+}
+
+/// Heap sort of `lo..hi`, for methods that sort a chunk of the array as a single
+/// step of their own.
+fn heap_sort(int: &Interface, lo: usize, hi: usize) {
+    let len = hi - lo;
+    for start in (0..len / 2).rev() {
+        sift_down(int, lo, start, len, Lines::Unmarked);
+    }
+    for end in (1..len).rev() {
+        int.swap(lo, lo + end);
+        sift_down(int, lo, 0, end, Lines::Unmarked);
+    }
+}
+
+/// Page layout of the simulated disk, as seen by the external methods.
+struct Pages {
+    /// Length of the array and of every scratch file.
+    len: usize,
+    page: usize,
+}
+
+impl Pages {
+    /// Whether `address` is the first element of its page.
+    const fn first(&self, address: usize) -> bool {
+        (address % self.len).is_multiple_of(self.page)
+    }
+
+    /// Whether `address` is the last element of its page.
+    const fn last(&self, address: usize) -> bool {
+        let offset = address % self.len + 1;
+        offset.is_multiple_of(self.page) || offset == self.len
+    }
+
+    /// Writes back the pages of `lo..hi`.
+    fn write(&self, int: &Interface, lo: usize, hi: usize) {
+        for i in (lo..hi).filter(|&i| self.last(i) || i + 1 == hi) {
+            int.evict(i);
+        }
+    }
+
+    /// Moves `len` elements from `from` to `to`, writing each page of `to` once
+    /// it is full and dropping those of `from` once emptied. Pages of `to` are
+    /// not read: everything before `to` on its page must be in memory already.
+    fn transfer(&self, int: &Interface, from: usize, to: usize, len: usize) {
+        for k in 0..len {
+            if self.first(to + k) {
+                int.claim(to + k);
+            }
+            int.swap(from + k, to + k);
+            if self.last(from + k) || k + 1 == len {
+                int.discard(from + k);
+            }
+            if self.last(to + k) {
+                int.evict(to + k);
+            }
+        }
+    }
+
+    /// Merges the runs `start..end` into `out`, one page of each in memory,
+    /// and returns the end of the output. Ties go to the earlier run. Output
+    /// pages are written once full, so the last one may stay in memory, and
+    /// are not read, as in `transfer`.
+    fn merge(&self, int: &Interface, runs: &mut [(usize, usize)], mut out: usize) -> usize {
+        loop {
+            let mut smallest: Option<usize> = None;
+            for r in (0..runs.len()).filter(|&r| runs[r].0 < runs[r].1) {
+                if smallest.is_none_or(|s| int.compare(runs[r].0, runs[s].0).is_lt()) {
+                    smallest = Some(r);
+                }
+            }
+            let Some(r) = smallest else {
+                return out;
+            };
+            let i = runs[r].0;
+            if self.first(out) {
+                int.claim(out);
+            }
+            int.swap(i, out);
+            if self.last(i) || i + 1 == runs[r].1 {
+                int.discard(i);
+            }
+            if self.last(out) {
+                int.evict(out);
+            }
+            runs[r].0 += 1;
+            out += 1;
+        }
+    }
+}
+
+/// Random number generator for one run, seeded with `--seed` when given.
+pub fn rng() -> StdRng {
+    GLOBAL_STATE
+        .lock()
+        .unwrap()
+        .seed
+        .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
 
 pub static MODIFIERS: &[Method] = &[
     Method {
         name: "shuffle",
         func: |int| {
             let len = int.len();
+            let mut rng = rng();
             for i in 0..len {
-                let j = rand::thread_rng().gen_range(i..len);
+                let j = rng.gen_range(i..len);
+                int.line(3);
                 int.swap(i, j);
             }
         },
+        code: &[
+            "for i in 0 .. n:",
+            "    j = random(i .. n)",
+            "    swap(i, j)",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n)",
+            worst: "O(n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description:
+                "Fisher–Yates shuffle: swaps every position with a uniformly random later one.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 2, §3.4.2"#,
+                "https://en.wikipedia.org/wiki/Fisher%E2%80%93Yates_shuffle",
+            ],
+        },
     },
     Method {
         name: "invert",
@@ -18,9 +261,23 @@ pub static MODIFIERS: &[Method] = &[
             let len = int.len();
             let half = len / 2;
             for i in 0..half {
+                int.line(2);
                 int.swap(i, len - i - 1);
             }
         },
+        code: &["for i in 0 .. n / 2:", "    swap(i, n - i - 1)"],
+        info: Info {
+            best: "O(n)",
+            average: "O(n)",
+            worst: "O(n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Reverses the array by swapping mirrored positions.",
+            references: &[],
+        },
     },
     Method {
         name: "pyramid",
@@ -50,11 +307,31 @@ pub static MODIFIERS: &[Method] = &[
             for i in 0..len {
                 while target_pos[i] != i {
                     let swap_with = target_pos[i];
+                    int.line(4);
                     int.swap(i, swap_with);
                     target_pos.swap(i, swap_with);
                 }
             }
         },
+        code: &[
+            "target = position of each element in the pyramid",
+            "for i in 0 .. n:",
+            "    while target[i] != i:",
+            "        swap(i, target[i])",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n)",
+            worst: "O(n)",
+            bounded: true,
+            memory: "O(n)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description:
+                "Arranges the values so they rise towards the middle and fall off on both sides.",
+            references: &[],
+        },
     },
 ];
 
@@ -64,9 +341,12 @@ pub static METHODS: &[Method] = &[
         func: |int| {
             let len = int.len();
             for i in 0..len {
+                int.phase(&format!("pass {}", i + 1));
                 let mut sorted = true;
                 for j in 0..len - i - 1 {
-                    if int.read(j) > int.read(j + 1) {
+                    int.line(4);
+                    if int.compare(j, j + 1).is_gt() {
+                        int.line(5);
                         int.swap(j, j + 1);
                         sorted = false;
                     }
@@ -76,15 +356,45 @@ pub static METHODS: &[Method] = &[
                 }
             }
         },
+        code: &[
+            "for i in 0 .. n:",
+            "    sorted = true",
+            "    for j in 0 .. n - i - 1:",
+            "        if a[j] > a[j + 1]:",
+            "            swap(j, j + 1)",
+            "            sorted = false",
+            "    if sorted: break",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: true,
+            in_place: true,
+            adaptive: true,
+            description: "Repeatedly swaps adjacent out-of-order pairs, stopping after a pass without swaps.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.2.2"#,
+                "https://en.wikipedia.org/wiki/Bubble_sort",
+            ],
+        },
     },
     Method {
         name: "bogo",
         func: |int| {
             let len = int.len();
+            if len == 0 {
+                return;
+            }
+            let mut rng = rng();
             loop {
+                int.phase("check");
                 let mut sorted = true;
                 for i in 0..(len - 1) {
-                    if int.read(i) > int.read(i + 1) {
+                    int.line(2);
+                    if int.compare(i, i + 1).is_gt() {
                         sorted = false;
                         break;
                     }
@@ -93,41 +403,113 @@ pub static METHODS: &[Method] = &[
                     return;
                 }
 
+                int.phase("shuffle");
                 for i in 0..len {
-                    let j = rand::thread_rng().gen_range(i..len);
+                    let j = rng.gen_range(i..len);
+                    int.line(6);
                     int.swap(i, j);
                 }
             }
         },
+        code: &[
+            "loop:",
+            "    if a is sorted:",
+            "        return",
+            "    for i in 0 .. n:",
+            "        j = random(i .. n)",
+            "        swap(i, j)",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n·n!)",
+            worst: "unbounded",
+            bounded: false,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Shuffles the array until it happens to be sorted.",
+            references: &[
+                "https://en.wikipedia.org/wiki/Bogosort",
+            ],
+        },
     },
     Method {
         name: "quick",
         func: |int| {
-            fn quick_sort(int: &Interface, lo: usize, hi: usize) {
-                if lo < hi {
+            fn quick_sort(int: &Interface, mut lo: usize, mut hi: usize) {
+                while lo < hi {
+                    int.phase(&format!("partition {lo}..={hi}"));
+                    int.line(3);
                     let pivot = int.read(lo);
                     let mut i = lo;
                     let mut j = hi;
 
                     let p = loop {
-                        while int.read(i) < pivot {
+                        int.line(6);
+                        while i < hi && int.compare_to(i, pivot).is_lt() {
                             i += 1;
                         }
-                        while int.read(j) > pivot {
+                        int.line(7);
+                        while j > lo && int.compare_to(j, pivot).is_gt() {
                             j -= 1;
                         }
                         if i >= j {
                             break j;
                         }
+                        int.line(9);
                         int.swap(i, j);
+                        i += 1;
+                        j -= 1;
                     };
 
-                    quick_sort(int, lo, p);
-                    quick_sort(int, p + 1, hi);
+                    // An inconsistent comparator could leave `p == hi`; clamp it so
+                    // both halves always shrink.
+                    let p = p.min(hi - 1);
+                    if p - lo < hi - p {
+                        quick_sort(int, lo, p);
+                        lo = p + 1;
+                    } else {
+                        quick_sort(int, p + 1, hi);
+                        hi = p;
+                    }
                 }
             }
 
-            quick_sort(&int, 0, int.len() - 1);
+            let len = int.len();
+            if len == 0 {
+                return;
+            }
+            quick_sort(&int, 0, len - 1);
+        },
+        code: &[
+            "quick_sort(lo, hi):",
+            "    while lo < hi:",
+            "        pivot = a[lo]",
+            "        i = lo, j = hi",
+            "        loop:",
+            "            while i < hi and a[i] < pivot: i += 1",
+            "            while j > lo and a[j] > pivot: j -= 1",
+            "            if i >= j: break",
+            "            swap(i, j)",
+            "            i += 1, j -= 1",
+            "        quick_sort the smaller of [lo, j] and [j + 1, hi]",
+            "        continue with the larger one",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(log n)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Partitions around the first element with Hoare's scheme and recurses into both halves.",
+            references: &[
+                r#"Hoare, "Quicksort", The Computer Journal 5(1), 1962"#,
+                "https://en.wikipedia.org/wiki/Quicksort",
+            ],
         },
     },
     Method {
@@ -135,15 +517,42 @@ pub static METHODS: &[Method] = &[
         func: |int| {
             let len = int.len();
             for i in 1..len {
+                int.line(2);
                 let key = int.read(i);
                 let mut j = i;
 
-                while j > 0 && int.read(j - 1) > key {
+                int.line(4);
+                while j > 0 && int.compare_to(j - 1, key).is_gt() {
+                    int.line(5);
                     int.swap(j, j - 1);
                     j -= 1;
+                    int.line(4);
                 }
             }
         },
+        code: &[
+            "for i in 1 .. n:",
+            "    key = a[i]",
+            "    j = i",
+            "    while j > 0 and a[j - 1] > key:",
+            "        swap(j, j - 1)",
+            "        j -= 1",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: true,
+            in_place: true,
+            adaptive: true,
+            description: "Grows a sorted prefix by sinking each new element into place.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.2.1"#,
+                "https://en.wikipedia.org/wiki/Insertion_sort",
+            ],
+        },
     },
     Method {
         name: "selection",
@@ -151,18 +560,42 @@ pub static METHODS: &[Method] = &[
             let len = int.len();
             for i in 0..len {
                 let mut min_index = i;
-                let mut min_value = int.read(i);
                 for j in (i + 1)..len {
-                    if int.read(j) < min_value {
+                    int.line(4);
+                    if int.compare(j, min_index).is_lt() {
                         min_index = j;
-                        min_value = int.read(j);
                     }
                 }
                 if min_index != i {
+                    int.line(7);
                     int.swap(i, min_index);
                 }
             }
         },
+        code: &[
+            "for i in 0 .. n:",
+            "    min = i",
+            "    for j in i + 1 .. n:",
+            "        if a[j] < a[min]:",
+            "            min = j",
+            "    if min != i:",
+            "        swap(i, min)",
+        ],
+        info: Info {
+            best: "O(n²)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Swaps the minimum of the unsorted suffix to its front.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.2.3"#,
+                "https://en.wikipedia.org/wiki/Selection_sort",
+            ],
+        },
     },
     Method {
         name: "gnome",
@@ -170,14 +603,40 @@ pub static METHODS: &[Method] = &[
             let len = int.len();
             let mut i = 0;
             while i < len {
-                if i == 0 || int.read(i - 1) <= int.read(i) {
+                int.line(3);
+                if i == 0 || int.compare(i - 1, i).is_le() {
                     i += 1;
                 } else {
+                    int.line(6);
                     int.swap(i, i - 1);
                     i -= 1;
                 }
             }
         },
+        code: &[
+            "i = 0",
+            "while i < n:",
+            "    if i == 0 or a[i - 1] <= a[i]:",
+            "        i += 1",
+            "    else:",
+            "        swap(i, i - 1)",
+            "        i -= 1",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: true,
+            in_place: true,
+            adaptive: true,
+            description: "Walks forward while pairs are ordered and swaps backwards when they are not.",
+            references: &[
+                r#"Sarbazi-Azad, "Stupid Sort: A new sorting algorithm", 2000"#,
+                "https://en.wikipedia.org/wiki/Gnome_sort",
+            ],
+        },
     },
     Method {
         name: "shell",
@@ -185,29 +644,66 @@ pub static METHODS: &[Method] = &[
             let len = int.len();
             let mut gap = len / 2;
             while gap > 0 {
+                int.phase(&format!("gap {gap}"));
                 for i in gap..len {
+                    int.line(4);
                     let temp = int.read(i);
                     let mut j = i;
-                    while j >= gap && int.read(j - gap) > temp {
+                    int.line(6);
+                    while j >= gap && int.compare_to(j - gap, temp).is_gt() {
+                        int.line(7);
                         int.swap(j, j - gap);
                         j -= gap;
+                        int.line(6);
                     }
                 }
                 gap /= 2;
             }
         },
+        code: &[
+            "gap = n / 2",
+            "while gap > 0:",
+            "    for i in gap .. n:",
+            "        temp = a[i]",
+            "        j = i",
+            "        while j >= gap and a[j - gap] > temp:",
+            "            swap(j, j - gap)",
+            "            j -= gap",
+            "    gap /= 2",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n^1.5)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: true,
+            description: "Insertion sort over gaps halving from n / 2 down to 1.",
+            references: &[
+                r#"Shell, "A high-speed sorting procedure", CACM 2(7), 1959"#,
+                "https://en.wikipedia.org/wiki/Shellsort",
+            ],
+        },
     },
     Method {
         name: "cocktail",
         func: |int| {
             let len = int.len();
+            if len == 0 {
+                return;
+            }
             let mut start = 0;
             let mut end = len - 1;
             let mut swapped = true;
             while swapped {
                 swapped = false;
+                int.phase("forward pass");
                 for i in start..end {
-                    if int.read(i) > int.read(i + 1) {
+                    int.line(6);
+                    if int.compare(i, i + 1).is_gt() {
+                        int.line(7);
                         int.swap(i, i + 1);
                         swapped = true;
                     }
@@ -217,8 +713,11 @@ pub static METHODS: &[Method] = &[
                 }
                 swapped = false;
                 end -= 1;
+                int.phase("backward pass");
                 for i in (start..end).rev() {
-                    if int.read(i) > int.read(i + 1) {
+                    int.line(13);
+                    if int.compare(i, i + 1).is_gt() {
+                        int.line(14);
                         int.swap(i, i + 1);
                         swapped = true;
                     }
@@ -226,6 +725,579 @@ pub static METHODS: &[Method] = &[
                 start += 1;
             }
         },
+        code: &[
+            "start = 0, end = n - 1",
+            "swapped = true",
+            "while swapped:",
+            "    swapped = false",
+            "    for i in start .. end:",
+            "        if a[i] > a[i + 1]:",
+            "            swap(i, i + 1)",
+            "            swapped = true",
+            "    if not swapped: break",
+            "    swapped = false",
+            "    end -= 1",
+            "    for i in end - 1 down to start:",
+            "        if a[i] > a[i + 1]:",
+            "            swap(i, i + 1)",
+            "            swapped = true",
+            "    start += 1",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: true,
+            in_place: true,
+            adaptive: true,
+            description: "Bubble sort alternating between forward and backward passes.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.2.2"#,
+                "https://en.wikipedia.org/wiki/Cocktail_shaker_sort",
+            ],
+        },
+    },
+    Method {
+        name: "heap",
+        func: |int| {
+            let len = int.len();
+            int.phase("heapify");
+            for start in (0..len / 2).rev() {
+                sift_down(&int, 0, start, len, Lines::Marked);
+            }
+            int.phase("extract");
+            for end in (1..len).rev() {
+                int.line(4);
+                int.swap(0, end);
+                sift_down(&int, 0, 0, end, Lines::Marked);
+            }
+        },
+        code: &[
+            "for i in n / 2 - 1 down to 0:",
+            "    sift_down(i, n)",
+            "for end in n - 1 down to 1:",
+            "    swap(0, end)",
+            "    sift_down(0, end)",
+            "sift_down(root, end):",
+            "    while 2 * root + 1 < end:",
+            "        child = the larger child of root",
+            "        if a[root] >= a[child]: return",
+            "        swap(root, child)",
+            "        root = child",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Builds a max-heap in the array, then repeatedly moves its root behind the heap. Guaranteed O(n log n), but sift-down jumps between distant indices, which caches handle poorly.",
+            references: &[
+                r#"Williams, "Algorithm 232: Heapsort", Communications of the ACM 7(6), 1964"#,
+                "https://en.wikipedia.org/wiki/Heapsort",
+            ],
+        },
+    },
+    Method {
+        name: "parallel-merge",
+        func: |int| {
+            fn merge_sort(int: &Interface, lo: usize, hi: usize, depth: u32) {
+                if hi - lo < 2 {
+                    return;
+                }
+                let mid = lo + (hi - lo) / 2;
+                if depth < THREAD_DEPTH {
+                    int.join(
+                        |int| merge_sort(int, lo, mid, depth + 1),
+                        |int| merge_sort(int, mid, hi, depth + 1),
+                    );
+                } else {
+                    merge_sort(int, lo, mid, depth);
+                    merge_sort(int, mid, hi, depth);
+                }
+                merge(int, lo, mid, hi, depth);
+            }
+
+            fn merge(int: &Interface, lo: usize, mid: usize, hi: usize, depth: u32) {
+                if lo == mid || mid == hi {
+                    return;
+                }
+                if hi - lo == 2 {
+                    int.line(7);
+                    if int.compare(mid, lo).is_lt() {
+                        int.swap(lo, mid);
+                    }
+                    return;
+                }
+                int.line(8);
+                let (cut1, cut2) = if mid - lo > hi - mid {
+                    let cut1 = lo + (mid - lo) / 2;
+                    (cut1, partition_point(mid, hi, |k| int.compare(k, cut1).is_lt()))
+                } else {
+                    let cut2 = mid + (hi - mid) / 2;
+                    (partition_point(lo, mid, |k| int.compare(k, cut2).is_le()), cut2)
+                };
+                int.line(9);
+                reverse(int, cut1, mid);
+                reverse(int, mid, cut2);
+                reverse(int, cut1, cut2);
+                let mid = cut1 + (cut2 - mid);
+                if depth < THREAD_DEPTH {
+                    int.join(
+                        |int| merge(int, lo, cut1, mid, depth + 1),
+                        |int| merge(int, mid, cut2, hi, depth + 1),
+                    );
+                } else {
+                    merge(int, lo, cut1, mid, depth);
+                    merge(int, mid, cut2, hi, depth);
+                }
+            }
+
+            merge_sort(&int, 0, int.len(), 0);
+        },
+        code: &[
+            "merge_sort(lo, hi):",
+            "    if hi - lo < 2: return",
+            "    mid = (lo + hi) / 2",
+            "    in parallel: merge_sort(lo, mid), merge_sort(mid, hi)",
+            "    merge(lo, mid, hi)",
+            "merge(lo, mid, hi):",
+            "    if both runs hold one element: swap them if a[mid] < a[lo]",
+            "    halve the longer run, binary search its middle in the other",
+            "    rotate the section between the two cuts",
+            "    in parallel: merge the runs left and right of the cuts",
+        ],
+        info: Info {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            bounded: true,
+            memory: "O(log n)",
+            stable: true,
+            in_place: true,
+            adaptive: false,
+            description: "Merge sort that sorts the halves on separate threads. Runs are merged in place by rotating the section between two cuts, which splits every merge into two independent ones that run in parallel as well.",
+            references: &[
+                r#"Dudziński and Dydek, "On a stable minimum storage merging algorithm", Information Processing Letters 12(1), 1981"#,
+                "https://en.wikipedia.org/wiki/Merge_sort#Parallel_merge_sort",
+            ],
+        },
+    },
+    Method {
+        name: "parallel-quick",
+        func: |int| {
+            fn quick_sort(int: &Interface, mut lo: usize, mut hi: usize, depth: u32) {
+                while lo < hi {
+                    int.line(3);
+                    let pivot = int.read(lo);
+                    let mut i = lo;
+                    let mut j = hi;
+
+                    let p = loop {
+                        int.line(6);
+                        while i < hi && int.compare_to(i, pivot).is_lt() {
+                            i += 1;
+                        }
+                        int.line(7);
+                        while j > lo && int.compare_to(j, pivot).is_gt() {
+                            j -= 1;
+                        }
+                        if i >= j {
+                            break j;
+                        }
+                        int.line(9);
+                        int.swap(i, j);
+                        i += 1;
+                        j -= 1;
+                    };
+
+                    let p = p.min(hi - 1);
+                    if depth < THREAD_DEPTH {
+                        int.join(
+                            |int| quick_sort(int, lo, p, depth + 1),
+                            |int| quick_sort(int, p + 1, hi, depth + 1),
+                        );
+                        return;
+                    }
+                    if p - lo < hi - p {
+                        quick_sort(int, lo, p, depth);
+                        lo = p + 1;
+                    } else {
+                        quick_sort(int, p + 1, hi, depth);
+                        hi = p;
+                    }
+                }
+            }
+
+            let len = int.len();
+            if len > 0 {
+                quick_sort(&int, 0, len - 1, 0);
+            }
+        },
+        code: &[
+            "quick_sort(lo, hi):",
+            "    while lo < hi:",
+            "        pivot = a[lo]",
+            "        i = lo, j = hi",
+            "        loop:",
+            "            while i < hi and a[i] < pivot: i += 1",
+            "            while j > lo and a[j] > pivot: j -= 1",
+            "            if i >= j: break",
+            "            swap(i, j)",
+            "            i += 1, j -= 1",
+            "        near the top: in parallel quick_sort [lo, j] and [j + 1, hi]",
+            "        deeper: quick_sort the smaller one, continue with the larger",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(log n)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Quick sort that hands both partitions to separate threads near the top of the recursion. The first partition is sequential, so it bounds the speedup.",
+            references: &[
+                "https://en.wikipedia.org/wiki/Quicksort#Parallelization",
+            ],
+        },
+    },
+    Method {
+        name: "bitonic",
+        func: |int| run_network(&int, Kind::Bitonic),
+        code: &[
+            "for each layer of the network for n wires:",
+            "    in parallel for each comparator (i, j) of the layer:",
+            "        if a[i] > a[j]: swap(i, j)",
+            "",
+            "network(lo, n, up):",
+            "    network(lo, n / 2, not up) alongside network(lo + n / 2, n - n / 2, up)",
+            "    merge(lo, n, up)",
+            "merge(lo, n, up):",
+            "    m = greatest power of two below n",
+            "    layer: (i, i + m) for i in lo .. lo + n - m, reversed unless up",
+            "    merge(lo, m, up) alongside merge(lo + m, n - m, up)",
+        ],
+        info: Info {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Batcher's bitonic sorter, a fixed network of compare-exchanges. Sorting the halves in opposite directions makes a bitonic sequence, which the merge sorts with layers of independent compare-exchanges that are split among threads. This variant handles any n.",
+            references: &[
+                r#"Batcher, "Sorting networks and their applications", AFIPS Spring Joint Computer Conference, 1968"#,
+                "https://hwlang.de/algorithmen/sortieren/bitonic/oddn.htm",
+                "https://en.wikipedia.org/wiki/Bitonic_sorter",
+            ],
+        },
+    },
+    Method {
+        name: "odd-even-merge",
+        func: |int| run_network(&int, Kind::OddEvenMerge),
+        code: &[
+            "for each layer of the network for n wires:",
+            "    in parallel for each comparator (i, j) of the layer:",
+            "        if a[i] > a[j]: swap(i, j)",
+            "",
+            "network for N = n rounded up to a power of two, without wires ≥ n:",
+            "for p = 1, 2, 4, … < N:",
+            "    for k = p, p / 2, …, 1:",
+            "        layer: (i, i + k) for i ≥ k mod p in blocks of k every 2k,",
+            "               if i and i + k lie in the same block of 2p",
+        ],
+        info: Info {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Batcher's odd-even merge sort as a network: merges sorted halves by merging their even and odd subsequences and fixing up neighbours. Uses fewer comparators than the bitonic sorter at the same depth; the layers are split among threads.",
+            references: &[
+                r#"Batcher, "Sorting networks and their applications", AFIPS Spring Joint Computer Conference, 1968"#,
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.3.4"#,
+                "https://en.wikipedia.org/wiki/Batcher_odd%E2%80%93even_mergesort",
+            ],
+        },
+    },
+    Method {
+        name: "pairwise",
+        func: |int| run_network(&int, Kind::Pairwise),
+        code: &[
+            "for each layer of the network for n wires:",
+            "    in parallel for each comparator (i, j) of the layer:",
+            "        if a[i] > a[j]: swap(i, j)",
+            "",
+            "network for N = n rounded up to a power of two, without wires ≥ n:",
+            "for a = 1, 2, 4, … < N:",
+            "    layer: (b - a, b) for every b with bit a set",
+            "for a = N / 4, N / 8, …, 1 and e = 1, 3, 7, …:",
+            "    for d = e, e / 2, …, 1:",
+            "        layer: (b - d·a, b) for b ≥ (d + 1)·a in every other block of a",
+        ],
+        info: Info {
+            best: "O(n log² n)",
+            average: "O(n log² n)",
+            worst: "O(n log² n)",
+            bounded: true,
+            memory: "O(1)",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Parberry's pairwise sorting network: sorts pairs, pairs of pairs and so on, then merges them from the largest distance down. Same size and depth as odd-even merge sort, with a different wiring; the layers are split among threads.",
+            references: &[
+                r#"Parberry, "The pairwise sorting network", Parallel Processing Letters 2(2–3), 1992"#,
+                "https://en.wikipedia.org/wiki/Pairwise_sorting_network",
+            ],
+        },
+    },
+    Method {
+        name: "odd-even",
+        func: |int| {
+            let len = int.len();
+            let swapped = AtomicBool::new(false);
+            let mut quiet = 0;
+            for_each_layer(
+                &int,
+                |int, phase| {
+                    if phase > 0 {
+                        quiet = if swapped.swap(false, Ordering::Relaxed) {
+                            0
+                        } else {
+                            quiet + 1
+                        };
+                    }
+                    if phase >= len || quiet >= 2 {
+                        return None;
+                    }
+                    int.phase(if phase % 2 == 0 { "even pairs" } else { "odd pairs" });
+                    let first = phase % 2;
+                    Some((first, len.saturating_sub(first) / 2))
+                },
+                |int, &first, pair| {
+                    let i = first + 2 * pair;
+                    int.line(3);
+                    if int.compare(i, i + 1).is_gt() {
+                        int.swap(i, i + 1);
+                        swapped.store(true, Ordering::Relaxed);
+                    }
+                },
+            );
+        },
+        code: &[
+            "for phase in 0 .. n:",
+            "    in parallel for i in phase % 2, phase % 2 + 2, … < n - 1:",
+            "        if a[i] > a[i + 1]: swap(i, i + 1)",
+            "    stop after two phases without swaps",
+        ],
+        info: Info {
+            best: "O(n)",
+            average: "O(n²)",
+            worst: "O(n²)",
+            bounded: true,
+            memory: "O(1)",
+            stable: true,
+            in_place: true,
+            adaptive: true,
+            description: "Odd-even transposition sort: alternately compare-exchanges all even and all odd neighbour pairs. The pairs of a phase are disjoint and split among threads; n phases always suffice.",
+            references: &[
+                r#"Habermann, "Parallel neighbor-sort", Carnegie Mellon University, 1972"#,
+                "https://en.wikipedia.org/wiki/Odd%E2%80%93even_sort",
+            ],
+        },
+    },
+    Method {
+        name: "external-merge",
+        func: |int| {
+            let len = int.len();
+            let config = int.disk(1);
+            let pages = Pages {
+                len,
+                page: config.page,
+            };
+            let k = config.frames.max(Config::MIN_FRAMES) - 1;
+            let chunk = k * config.page;
+
+            // Form the runs where the last pass will not write.
+            let mut passes = 0;
+            let mut run = chunk;
+            while run < len {
+                run *= k;
+                passes += 1;
+            }
+            let mut input = passes % 2;
+            int.phase("runs");
+            for lo in (0..len).step_by(chunk) {
+                let hi = (lo + chunk).min(len);
+                int.line(3);
+                heap_sort(&int, lo, hi);
+                int.line(4);
+                if input == 0 {
+                    pages.write(&int, lo, hi);
+                } else {
+                    pages.transfer(&int, lo, len + lo, hi - lo);
+                }
+            }
+
+            let mut run = chunk;
+            for pass in 1..=passes {
+                int.phase(&format!("pass {pass}"));
+                for lo in (0..len).step_by(run * k) {
+                    let mut runs = (0..k)
+                        .map(|r| {
+                            let start = (lo + r * run).min(len);
+                            let end = (lo + (r + 1) * run).min(len);
+                            (input * len + start, input * len + end)
+                        })
+                        .collect::<Vec<_>>();
+                    int.line(7);
+                    pages.merge(&int, &mut runs, (1 - input) * len + lo);
+                }
+                int.line(9);
+                input = 1 - input;
+                run *= k;
+            }
+        },
+        code: &[
+            "k = M - 1 pages of memory for input, 1 for output",
+            "for each chunk of k pages:",
+            "    sort it in memory",
+            "    write it back, or to file 1 for an odd number of passes",
+            "while runs are shorter than the array:",
+            "    for each group of k runs:",
+            "        move the smallest head to the output page, writing",
+            "        full output pages and dropping used up input pages",
+            "    swap the roles of the array and file 1",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            bounded: true,
+            memory: "O(n)",
+            stable: false,
+            in_place: false,
+            adaptive: false,
+            description: "External merge sort: sorts runs the size of memory, then merges k runs at a time through one page of memory each, ping-ponging between the array and a scratch file. Every pass reads and writes each page once. Made for the simulated disk, whose page reads and writes it minimizes.",
+            references: &[
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.4.1"#,
+                "https://en.wikipedia.org/wiki/External_sorting",
+            ],
+        },
+    },
+    Method {
+        name: "polyphase",
+        func: |int| {
+            let len = int.len();
+            let config = int.disk(2);
+            let pages = Pages {
+                len,
+                page: config.page,
+            };
+            let chunk = (config.frames.max(Config::MIN_FRAMES) - 1) * config.page;
+            let count = len.div_ceil(chunk);
+            int.phase("runs");
+            if count <= 1 {
+                int.line(1);
+                heap_sort(&int, 0, len);
+                pages.write(&int, 0, len);
+                return;
+            }
+
+            // Run lengths queued on each file, where the next run starts and
+            // where the next one is written. Empty runs pad the distribution.
+            let mut runs: [VecDeque<usize>; 3] = Default::default();
+            let mut head = [0; 3];
+            let mut tail = [0; 3];
+            let (mut a, mut b) = (1, 0);
+            while a + b < count {
+                (a, b) = (a + b, a);
+            }
+            let dummies = a + b - count;
+            let padding = [0, dummies * a / (a + b), dummies - dummies * a / (a + b)];
+            runs[1].resize(padding[1], 0);
+            runs[2].resize(padding[2], 0);
+            for (r, lo) in (0..len).step_by(chunk).enumerate() {
+                let hi = (lo + chunk).min(len);
+                int.line(1);
+                heap_sort(&int, lo, hi);
+                let file = if r < a - padding[1] { 1 } else { 2 };
+                int.line(2);
+                pages.transfer(&int, lo, file * len + tail[file], hi - lo);
+                tail[file] += hi - lo;
+                runs[file].push_back(hi - lo);
+            }
+
+            let mut out = 0;
+            let mut phase = 1;
+            while runs.iter().map(VecDeque::len).sum::<usize>() > 1 {
+                int.phase(&format!("phase {phase}"));
+                let inputs = [(out + 1) % 3, (out + 2) % 3];
+                (head[out], tail[out]) = (0, 0);
+                while inputs.iter().all(|&f| !runs[f].is_empty()) {
+                    let mut group = inputs.map(|f| {
+                        let run = runs[f].pop_front().unwrap();
+                        let start = f * len + head[f];
+                        head[f] += run;
+                        (start, start + run)
+                    });
+                    let run = group.iter().map(|(start, end)| end - start).sum();
+                    int.line(4);
+                    pages.merge(&int, &mut group, out * len + tail[out]);
+                    tail[out] += run;
+                    runs[out].push_back(run);
+                }
+                if tail[out] > 0 {
+                    int.evict(out * len + tail[out] - 1);
+                }
+                int.line(5);
+                out = inputs.into_iter().find(|&f| runs[f].is_empty()).unwrap();
+                phase += 1;
+            }
+
+            let file = (0..3).find(|&f| !runs[f].is_empty()).unwrap();
+            if file != 0 {
+                int.phase("copy");
+                int.line(6);
+                pages.transfer(&int, file * len + head[file], 0, len);
+            }
+        },
+        code: &[
+            "sort runs of M - 1 pages in memory",
+            "deal them to files 1 and 2 by Fibonacci numbers, padded with empty runs",
+            "while more than one run is left:",
+            "    merge a run of each input file into the empty file",
+            "    until an input runs out; it becomes the next output",
+            "copy the result to the array if it ended up elsewhere",
+        ],
+        info: Info {
+            best: "O(n log n)",
+            average: "O(n log n)",
+            worst: "O(n log n)",
+            bounded: true,
+            memory: "O(n)",
+            stable: false,
+            in_place: false,
+            adaptive: false,
+            description: "Polyphase merge sort on three files: runs are dealt out by consecutive Fibonacci numbers, so that every phase merges until one input file runs out, which then takes the output. Avoids the copying passes of a balanced merge on few files. Made for the simulated disk.",
+            references: &[
+                r#"Gilstad, "Polyphase merge sorting: an advanced technique", Proceedings of the Eastern Joint Computer Conference, 1960"#,
+                r#"Knuth, "The Art of Computer Programming", Vol. 3, §5.4.2"#,
+                "https://en.wikipedia.org/wiki/Polyphase_merge_sort",
+            ],
+        },
     },
     #[cfg(feature = "fallible")]
     Method {
@@ -233,5 +1305,18 @@ pub static METHODS: &[Method] = &[
         func: |_int| {
             panic!("This is a test panic from the 'panic' method!");
         },
+        code: &["panic()"],
+        info: Info {
+            best: "-",
+            average: "-",
+            worst: "-",
+            bounded: true,
+            memory: "-",
+            stable: false,
+            in_place: true,
+            adaptive: false,
+            description: "Panics immediately, to exercise the crash dialog.",
+            references: &[],
+        },
     },
 ];
@@ -0,0 +1,255 @@
+use egui::{pos2, Color32, Painter, Rect, Shape, Stroke};
+use std::time::{Duration, Instant};
+
+/// Samples kept before every other one is dropped.
+const MAX_SAMPLES: usize = 1000;
+/// Shortest time between two samples.
+const MIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How far `data` is from being sorted.
+#[derive(Clone, Copy, Default)]
+pub struct Metrics {
+    len: usize,
+    /// Pairs of elements in the wrong order. For distinct values this is also
+    /// the Kendall tau distance to the sorted array.
+    pub inversions: u64,
+    /// Maximal non-decreasing runs.
+    pub runs: usize,
+    /// Length of the longest non-decreasing subsequence.
+    pub lis: usize,
+    /// Spearman's footrule: total distance of the elements from their sorted
+    /// positions, ties keeping their relative order.
+    pub footrule: u64,
+    /// Elements already at their sorted position.
+    pub in_place: usize,
+}
+
+impl Metrics {
+    /// Names and chart colors of the values returned by `progress`.
+    pub const SERIES: [(&'static str, Color32); 5] = [
+        ("inversions", Color32::RED),
+        ("runs", Color32::YELLOW),
+        ("LIS", Color32::GREEN),
+        ("footrule", Color32::LIGHT_BLUE),
+        ("in place", Color32::from_rgb(200, 120, 255)),
+    ];
+
+    pub fn measure(data: &[u32]) -> Self {
+        let mut order = (0..data.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&i| data[i]);
+        let (footrule, in_place) =
+            order
+                .iter()
+                .enumerate()
+                .fold((0, 0), |(footrule, in_place), (rank, &i)| {
+                    (
+                        footrule + rank.abs_diff(i) as u64,
+                        in_place + usize::from(rank == i),
+                    )
+                });
+
+        Self {
+            len: data.len(),
+            inversions: inversions(&mut data.to_vec()),
+            runs: 1 + data.windows(2).filter(|w| w[0] > w[1]).count(),
+            lis: lis(data),
+            footrule,
+            in_place,
+        }
+    }
+
+    /// Kendall's tau between `data` and the sorted array, from -1 for reversed
+    /// to 1 for sorted.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn kendall_tau(&self) -> f64 {
+        let pairs = self.len as f64 * (self.len as f64 - 1.0) / 2.0;
+        if pairs == 0.0 {
+            1.0
+        } else {
+            1.0 - 2.0 * self.inversions as f64 / pairs
+        }
+    }
+
+    /// Each metric scaled to 0 for its worst value and 1 for sorted, in the
+    /// order of `SERIES`.
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    pub fn progress(&self) -> [f32; 5] {
+        let n = self.len as f64;
+        let scale = |value: f64, worst: f64| {
+            if worst > 0.0 {
+                (1.0 - value / worst) as f32
+            } else {
+                1.0
+            }
+        };
+        [
+            scale(self.inversions as f64, n * (n - 1.0) / 2.0),
+            scale(self.runs as f64 - 1.0, n - 1.0),
+            scale(n - self.lis as f64, n - 1.0),
+            scale(self.footrule as f64, (n * n / 2.0).floor()),
+            scale(n - self.in_place as f64, n),
+        ]
+    }
+}
+
+/// Counts inversions with a merge sort, sorting `data` in the process.
+fn inversions(data: &mut [u32]) -> u64 {
+    if data.len() < 2 {
+        return 0;
+    }
+    let mid = data.len() / 2;
+    let mut count = inversions(&mut data[..mid]) + inversions(&mut data[mid..]);
+    let mut merged = Vec::with_capacity(data.len());
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < data.len() {
+        if data[j] < data[i] {
+            count += (mid - i) as u64;
+            merged.push(data[j]);
+            j += 1;
+        } else {
+            merged.push(data[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&data[i..mid]);
+    merged.extend_from_slice(&data[j..]);
+    data.copy_from_slice(&merged);
+    count
+}
+
+/// Patience sorting: `tails[k]` is the smallest tail of a non-decreasing
+/// subsequence of length `k + 1`.
+fn lis(data: &[u32]) -> usize {
+    let mut tails = Vec::new();
+    for &v in data {
+        let k = tails.partition_point(|&t| t <= v);
+        if k == tails.len() {
+            tails.push(v);
+        } else {
+            tails[k] = v;
+        }
+    }
+    tails.len()
+}
+
+/// `Metrics` sampled over the operations of a run.
+///
+/// Measuring takes O(n log n), so samples are spaced out to keep the time spent
+/// measuring at about a tenth of the wall time.
+#[derive(Default)]
+pub struct Tracker {
+    pub samples: Vec<(u64, Metrics)>,
+    /// Start time of the sampled run.
+    run: Option<Instant>,
+    next: Option<Instant>,
+}
+
+impl Tracker {
+    /// Whether to sample the array after `ops` operations of the run started
+    /// at `run`: if it is time for the next sample, or unconditionally if
+    /// `force` is set. A different `run` starts a new chart with an immediate
+    /// sample.
+    pub fn due(&mut self, run: Option<Instant>, ops: u64, force: bool) -> bool {
+        if run != self.run {
+            self.run = run;
+            self.samples.clear();
+            return true;
+        }
+        if self.samples.last().is_some_and(|&(last, _)| last == ops) {
+            return false;
+        }
+        force || self.next.is_none_or(|next| Instant::now() >= next)
+    }
+
+    /// Measures `data` after `ops` operations. Called without the state locked,
+    /// as measuring takes long on large arrays.
+    pub fn sample(&mut self, ops: u64, data: &[u32]) {
+        let start = Instant::now();
+        self.samples.push((ops, Metrics::measure(data)));
+        if self.samples.len() > MAX_SAMPLES {
+            // Keep the first and last samples.
+            let last = self.samples.len() - 1;
+            let mut i = 0;
+            self.samples.retain(|_| {
+                i += 1;
+                i % 2 == 1 || i - 1 == last
+            });
+        }
+        self.next = Some(start + (start.elapsed() * 10).max(MIN_INTERVAL));
+    }
+}
+
+/// Draws the `progress` of the samples as one line per metric over the
+/// operations, from 0 at the bottom of `area` to 1 at the top.
+#[allow(clippy::cast_precision_loss)]
+pub fn draw_chart(painter: &Painter, area: Rect, samples: &[(u64, Metrics)]) {
+    painter.rect_stroke(area, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+    let max_ops = samples.last().map_or(0, |&(ops, _)| ops).max(1) as f32;
+    let progress = samples
+        .iter()
+        .map(|(ops, metrics)| (*ops as f32 / max_ops, metrics.progress()))
+        .collect::<Vec<_>>();
+    for (series, (_, color)) in Metrics::SERIES.iter().enumerate() {
+        let points = progress
+            .iter()
+            .map(|(x, values)| {
+                pos2(
+                    area.width().mul_add(*x, area.min.x),
+                    area.height().mul_add(-values[series], area.max.y),
+                )
+            })
+            .collect();
+        painter.add(Shape::line(points, Stroke::new(1.5, *color)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_inversions() {
+        assert_eq!(inversions(&mut []), 0);
+        assert_eq!(inversions(&mut [1, 2, 3]), 0);
+        assert_eq!(inversions(&mut [3, 1, 2]), 2);
+        assert_eq!(inversions(&mut [4, 3, 2, 1]), 6);
+        // Equal elements are not inverted.
+        assert_eq!(inversions(&mut [2, 2, 1, 1]), 4);
+        let mut data = [5, 1, 4, 2, 3];
+        assert_eq!(inversions(&mut data), 6);
+        assert_eq!(data, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn longest_non_decreasing_subsequence() {
+        assert_eq!(lis(&[]), 0);
+        assert_eq!(lis(&[5, 4, 3]), 1);
+        assert_eq!(lis(&[3, 1, 2]), 2);
+        assert_eq!(lis(&[1, 2, 2, 1]), 3);
+        assert_eq!(lis(&[2, 6, 3, 4, 1, 5]), 4);
+    }
+
+    #[test]
+    fn footrule_and_in_place() {
+        let footrule = |data: &[u32]| {
+            let metrics = Metrics::measure(data);
+            (metrics.footrule, metrics.in_place)
+        };
+        assert_eq!(footrule(&[1, 2, 3]), (0, 3));
+        assert_eq!(footrule(&[3, 1, 2]), (4, 0));
+        assert_eq!(footrule(&[4, 3, 2, 1]), (8, 0));
+        assert_eq!(footrule(&[1, 3, 2, 4]), (2, 2));
+        // Ties keep their order: the 1s belong at 0 and 1.
+        assert_eq!(footrule(&[2, 1, 1]), (4, 0));
+    }
+
+    #[test]
+    fn measures_reversed() {
+        let metrics = Metrics::measure(&[4, 3, 2, 1]);
+        assert_eq!((metrics.inversions, metrics.runs, metrics.lis), (6, 4, 1));
+        assert!((metrics.kendall_tau() + 1.0).abs() < 1e-9);
+        assert!(metrics.progress().iter().all(|p| p.abs() < 1e-6));
+        let sorted = Metrics::measure(&[1, 2, 3, 4]);
+        assert!(sorted.progress().iter().all(|p| (p - 1.0).abs() < 1e-6));
+    }
+}
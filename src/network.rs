@@ -0,0 +1,327 @@
+use crate::sorter::{Cursor, Step};
+use egui::{pos2, Color32, Painter, Rect, Stroke};
+
+/// Largest number of wires drawn by `Diagram`.
+pub const MAX_WIRES: usize = 256;
+
+const WIRE: Color32 = Color32::DARK_GRAY;
+const ASCENDING: Color32 = Color32::LIGHT_BLUE;
+/// Comparators that move the smaller value to the lower wire.
+const DESCENDING: Color32 = Color32::from_rgb(255, 170, 80);
+const LAYER: Color32 = Color32::from_rgba_premultiplied(60, 60, 20, 60);
+const READ: Color32 = Color32::GREEN;
+const SWAP: Color32 = Color32::RED;
+
+/// A sorting network, built for any number of wires.
+///
+/// Comparators are pairs `(i, j)` that leave the smaller value on wire `i`.
+/// The comparators of a layer touch disjoint wires.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Batcher's bitonic sorter, in H. W. Lang's variant for any `n`.
+    Bitonic,
+    /// Batcher's odd-even merge sort.
+    OddEvenMerge,
+    /// Parberry's pairwise sorting network.
+    Pairwise,
+}
+
+impl Kind {
+    pub const ALL: [Self; 3] = [Self::Bitonic, Self::OddEvenMerge, Self::Pairwise];
+
+    /// Name of the method in `METHODS` that runs this network.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bitonic => "bitonic",
+            Self::OddEvenMerge => "odd-even-merge",
+            Self::Pairwise => "pairwise",
+        }
+    }
+
+    /// Number of layers of the network for `n` wires.
+    pub const fn depth(self, n: usize) -> usize {
+        match self {
+            Self::Bitonic => bitonic_depth(n),
+            Self::OddEvenMerge | Self::Pairwise => {
+                // Both have a layer per pair of powers of two k <= p < N.
+                let bits = n.next_power_of_two().trailing_zeros() as usize;
+                bits * (bits + 1) / 2
+            }
+        }
+    }
+
+    /// The comparators of layer `d` of the network for `n` wires.
+    ///
+    /// Layers are generated one at a time so that large arrays can be sorted
+    /// without holding the whole network.
+    pub fn layer(self, n: usize, d: usize) -> Vec<(usize, usize)> {
+        let mut gates = Vec::new();
+        match self {
+            Self::Bitonic => bitonic_layer(&mut gates, 0, n, true, d),
+            // The networks for the next power of two sort any prefix of their
+            // wires once comparators with the rest are dropped: those wires
+            // would hold values larger than all others and never move.
+            Self::OddEvenMerge => odd_even_layer(&mut gates, n.next_power_of_two(), d),
+            Self::Pairwise => pairwise_layer(&mut gates, n.next_power_of_two(), d),
+        }
+        gates.retain(|&(i, j)| i.max(j) < n);
+        gates
+    }
+}
+
+/// Layers of a bitonic merge of `n` wires: log2(n), rounded up.
+const fn bitonic_merge_depth(n: usize) -> usize {
+    n.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Layers of a bitonic sort of `n` wires. The larger half determines how long
+/// the halves take side by side, then they are merged.
+const fn bitonic_depth(n: usize) -> usize {
+    if n < 2 {
+        0
+    } else {
+        bitonic_depth(n - n / 2) + bitonic_merge_depth(n)
+    }
+}
+
+fn bitonic_layer(gates: &mut Vec<(usize, usize)>, lo: usize, n: usize, up: bool, d: usize) {
+    if n < 2 {
+        return;
+    }
+    let halves = bitonic_depth(n - n / 2);
+    if d < halves {
+        let m = n / 2;
+        bitonic_layer(gates, lo, m, !up, d);
+        bitonic_layer(gates, lo + m, n - m, up, d);
+    } else {
+        bitonic_merge_layer(gates, lo, n, up, d - halves);
+    }
+}
+
+fn bitonic_merge_layer(gates: &mut Vec<(usize, usize)>, lo: usize, n: usize, up: bool, d: usize) {
+    if n < 2 {
+        return;
+    }
+    let m = n.next_power_of_two() / 2;
+    if d == 0 {
+        gates.extend((lo..lo + n - m).map(|i| if up { (i, i + m) } else { (i + m, i) }));
+    } else {
+        bitonic_merge_layer(gates, lo, m, up, d - 1);
+        bitonic_merge_layer(gates, lo + m, n - m, up, d - 1);
+    }
+}
+
+/// The `d`th pair `(p, k)` of powers of two with `k <= p < n`, ordered by
+/// ascending `p` and descending `k`.
+const fn stage(n: usize, d: usize) -> Option<(usize, usize)> {
+    let mut p = 1;
+    let mut d = d;
+    while p < n {
+        let ks = p.trailing_zeros() as usize + 1;
+        if d < ks {
+            return Some((p, p >> d));
+        }
+        d -= ks;
+        p *= 2;
+    }
+    None
+}
+
+/// Names follow Batcher's formulation.
+#[allow(clippy::many_single_char_names)]
+fn odd_even_layer(gates: &mut Vec<(usize, usize)>, n: usize, d: usize) {
+    let Some((p, k)) = stage(n, d) else { return };
+    let mut j = k % p;
+    while j + k < n {
+        for i in 0..k.min(n - j - k) {
+            if (i + j) / (2 * p) == (i + j + k) / (2 * p) {
+                gates.push((i + j, i + j + k));
+            }
+        }
+        j += 2 * k;
+    }
+}
+
+/// Names follow Parberry's formulation.
+#[allow(clippy::many_single_char_names)]
+fn pairwise_layer(gates: &mut Vec<(usize, usize)>, n: usize, d: usize) {
+    let bits = n.trailing_zeros() as usize;
+    if d < bits {
+        // Compare b - a and b for every b whose bit a is set, ordering pairs,
+        // then pairs of pairs and so on.
+        let a = 1 << d;
+        gates.extend((a..n).filter(|b| b & a != 0).map(|b| (b - a, b)));
+        return;
+    }
+    // Then merge, with a = n / 4, n / 8, …, 1 and dist = e, e / 2, …, 1 where
+    // e = 1, 3, 7, …
+    let mut d = d - bits;
+    let (mut a, mut e) = (n / 4, 1);
+    while a > 0 {
+        let mut dist = e;
+        while dist > 0 {
+            if d == 0 {
+                let mut b = (dist + 1) * a;
+                let mut c = 0;
+                while b < n {
+                    gates.push((b - dist * a, b));
+                    b += 1;
+                    c += 1;
+                    if c == a {
+                        c = 0;
+                        b += a;
+                    }
+                }
+                return;
+            }
+            d -= 1;
+            dist /= 2;
+        }
+        a /= 2;
+        e = 2 * e + 1;
+    }
+}
+
+/// A network laid out for drawing.
+pub struct Diagram {
+    pub kind: Kind,
+    pub wires: usize,
+    layers: Vec<Vec<(usize, usize)>>,
+    /// Column of each comparator, so that comparators of a layer spanning
+    /// overlapping wires are drawn side by side.
+    columns: Vec<Vec<usize>>,
+    /// Columns taken by all layers, including a gap after each.
+    width: usize,
+}
+
+impl Diagram {
+    pub fn new(kind: Kind, wires: usize) -> Self {
+        let layers = (0..kind.depth(wires))
+            .map(|d| kind.layer(wires, d))
+            .collect::<Vec<_>>();
+        let mut columns = Vec::new();
+        let mut width = 0;
+        for layer in &layers {
+            // Interval coloring: take comparators by lower wire and put each in
+            // the first column whose last comparator ends above it.
+            let mut order = (0..layer.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&g| layer[g].0.min(layer[g].1));
+            let mut ends: Vec<usize> = Vec::new();
+            let mut layer_columns = vec![0; layer.len()];
+            for g in order {
+                let (i, j) = layer[g];
+                let free = ends.iter().position(|&end| end < i.min(j));
+                let column = free.unwrap_or(ends.len());
+                if column == ends.len() {
+                    ends.push(0);
+                }
+                ends[column] = i.max(j);
+                layer_columns[g] = width + column;
+            }
+            width += ends.len() + 1;
+            columns.push(layer_columns);
+        }
+        Self {
+            kind,
+            wires,
+            layers,
+            columns,
+            width,
+        }
+    }
+
+    /// Draws the wires left to right, wire 0 at the top, with the comparators
+    /// of `layer` highlighted and those the `cursors` are working on colored as
+    /// in the bar graph.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn draw(
+        &self,
+        painter: &Painter,
+        area: Rect,
+        layer: Option<usize>,
+        cursors: &[Option<Cursor>],
+    ) {
+        let column_w = area.width() / self.width.max(1) as f32;
+        let wire_h = area.height() / self.wires.max(1) as f32;
+        let x = |column: usize| (column as f32 + 0.5).mul_add(column_w, area.min.x);
+        let y = |wire: usize| (wire as f32 + 0.5).mul_add(wire_h, area.min.y);
+
+        if let Some(columns) = layer.and_then(|layer| self.columns.get(layer)) {
+            let first = columns.iter().min().copied().unwrap_or(0);
+            let last = columns.iter().max().copied().unwrap_or(0);
+            painter.rect_filled(
+                Rect::from_min_max(
+                    pos2(x(first) - column_w / 2.0, area.min.y),
+                    pos2(x(last) + column_w / 2.0, area.max.y),
+                ),
+                0.0,
+                LAYER,
+            );
+        }
+        for wire in 0..self.wires {
+            painter.hline(area.x_range(), y(wire), Stroke::new(1.0, WIRE));
+        }
+
+        let steps = cursors
+            .iter()
+            .flatten()
+            .filter_map(|cursor| cursor.step.as_ref())
+            .collect::<Vec<_>>();
+        let radius = (column_w.min(wire_h) * 0.4).clamp(1.0, 3.0);
+        for (d, (gates, columns)) in self.layers.iter().zip(&self.columns).enumerate() {
+            for (&(i, j), &column) in gates.iter().zip(columns) {
+                let mut color = if i < j { ASCENDING } else { DESCENDING };
+                if layer == Some(d) {
+                    for step in &steps {
+                        match **step {
+                            Step::Compare(a, b) if (a, b) == (i, j) || (a, b) == (j, i) => {
+                                color = READ;
+                            }
+                            Step::Swap(a, b) if (a, b) == (i, j) || (a, b) == (j, i) => {
+                                color = SWAP;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                let (top, bottom) = (pos2(x(column), y(i)), pos2(x(column), y(j)));
+                painter.line_segment([top, bottom], Stroke::new(1.0, color));
+                painter.circle_filled(top, radius, color);
+                painter.circle_filled(bottom, radius, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// By the zero-one principle a network sorts every input if it sorts
+    /// every input of zeros and ones.
+    #[test]
+    fn sorts_all_zero_one_inputs() {
+        for kind in Kind::ALL {
+            for n in 1..=12 {
+                let layers = (0..kind.depth(n))
+                    .map(|d| kind.layer(n, d))
+                    .collect::<Vec<_>>();
+                for layer in &layers {
+                    let mut wires = layer.iter().flat_map(|&(i, j)| [i, j]).collect::<Vec<_>>();
+                    wires.sort_unstable();
+                    wires.dedup();
+                    assert_eq!(wires.len(), 2 * layer.len(), "{} n={n}", kind.name());
+                }
+                for input in 0..1_u32 << n {
+                    let mut bits = (0..n).map(|k| input >> k & 1).collect::<Vec<_>>();
+                    for &(i, j) in layers.iter().flatten() {
+                        if bits[i] > bits[j] {
+                            bits.swap(i, j);
+                        }
+                    }
+                    assert!(bits.is_sorted(), "{} n={n} input={input:0n$b}", kind.name());
+                }
+            }
+        }
+    }
+}
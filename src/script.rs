@@ -0,0 +1,1183 @@
+use crate::sorter::{Info, Interface, Method};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::resume_unwind;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Deepest nesting of function calls a script may reach.
+const MAX_DEPTH: usize = 10_000;
+/// Deepest nesting of blocks and expressions the parser accepts, which bounds
+/// its recursion on the UI thread.
+const MAX_NESTING: usize = 64;
+
+/// Shown in the editor until the user writes their own.
+pub const EXAMPLE: &str = "\
+// Insertion sort. Operations on the array:
+//   len(), read(i), write(i, value), swap(i, j),
+//   compare(i, j) and compare_to(i, value), which return -1, 0 or 1,
+//   phase(\"label\")
+// Local arrays: [a, b, c], [value; n], len(a), push(a, value), pop(a)
+for i in 1..len() {
+    let j = i;
+    while j > 0 && compare(j - 1, j) > 0 {
+        swap(j - 1, j);
+        j -= 1;
+    }
+}
+";
+
+/// Runs the program loaded with `load`.
+pub static METHOD: Method = Method {
+    name: "script",
+    func: |int| {
+        let program = PROGRAM.lock().unwrap().clone();
+        if let Some(program) = program {
+            Machine::new(&int, &program).run();
+        }
+    },
+    code: &[],
+    info: Info {
+        best: "-",
+        average: "-",
+        worst: "-",
+        bounded: true,
+        memory: "-",
+        stable: false,
+        in_place: false,
+        adaptive: false,
+        description: "A method written in the script editor.",
+        references: &[],
+    },
+};
+
+/// The program `METHOD` runs.
+static PROGRAM: Mutex<Option<Arc<Program>>> = Mutex::new(None);
+
+/// Compiles `source` into the program run by `METHOD`.
+pub fn load(source: &str) -> Result<(), Error> {
+    let mut program = Parser::new(source)?.program()?;
+    program.check()?;
+    source.clone_into(&mut program.source);
+    *PROGRAM.lock().unwrap() = Some(Arc::new(program));
+    Ok(())
+}
+
+/// Source lines of the loaded program, for the code panel.
+pub fn lines() -> Vec<String> {
+    PROGRAM
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(Vec::new, |p| p.source.lines().map(String::from).collect())
+}
+
+/// A syntax error, or a runtime error raised as the failure of a run.
+#[derive(Debug)]
+pub struct Error {
+    /// 1-based line of the script.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Str(String),
+    Ident(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(n) => write!(f, "{n}"),
+            Self::Str(s) => write!(f, "{s:?}"),
+            Self::Ident(name) => write!(f, "{name}"),
+            Self::Symbol(symbol) => write!(f, "{symbol}"),
+            Self::End => write!(f, "end of script"),
+        }
+    }
+}
+
+/// Longest first, so that `..` isn't read as two dots.
+const SYMBOLS: [&str; 26] = [
+    "..", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "(", ")", "{", "}", "[", "]", ",", ";",
+    "=", "<", ">", "+", "-", "*", "/", "%", "!",
+];
+
+const KEYWORDS: [&str; 12] = [
+    "fn", "let", "if", "else", "while", "for", "in", "return", "break", "continue", "true", "false",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let mut tokens = Vec::new();
+    for (k, text) in source.lines().enumerate() {
+        let line = k + 1;
+        let error = |message: String| Error { line, message };
+        let mut rest = text;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with("//") {
+                break;
+            }
+            let c = rest.chars().next().unwrap();
+            let (token, len) = if c.is_ascii_digit() {
+                let len = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let n = rest[..len]
+                    .parse()
+                    .map_err(|_| error(format!("number too large: {}", &rest[..len])))?;
+                (Token::Int(n), len)
+            } else if c.is_alphabetic() || c == '_' {
+                let len = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                (Token::Ident(rest[..len].to_owned()), len)
+            } else if c == '"' {
+                let end = rest[1..]
+                    .find('"')
+                    .ok_or_else(|| error(String::from("unterminated string")))?;
+                (Token::Str(rest[1..=end].to_owned()), end + 2)
+            } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+                (Token::Symbol(symbol), symbol.len())
+            } else {
+                return Err(error(format!("unexpected character {c:?}")));
+            };
+            tokens.push((token, line));
+            rest = &rest[len..];
+        }
+    }
+    let last = source.lines().count().max(1);
+    tokens.push((Token::End, last));
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+enum Expr {
+    Int(i64),
+    Str(String),
+    Var(String),
+    /// `[a, b, c]`
+    Array(Vec<Self>),
+    /// `[value; count]`
+    Repeat(Box<Self>, Box<Self>),
+    Index(Box<Self>, Box<Self>),
+    Call(String, Vec<Self>),
+    Neg(Box<Self>),
+    Not(Box<Self>),
+    Binary(Op, Box<Self>, Box<Self>),
+}
+
+enum Target {
+    Var(String),
+    Index(Expr, Expr),
+}
+
+struct Stmt {
+    line: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Let(String, Expr),
+    Assign(Target, Expr),
+    Expr(Expr),
+    /// Conditions with their blocks, then the `else` block.
+    If(Vec<(Expr, Vec<Stmt>)>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    For(String, Expr, Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+}
+
+struct Function {
+    params: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+/// A parsed script: functions and the statements run at the top level.
+pub struct Program {
+    source: String,
+    functions: HashMap<String, Function>,
+    main: Vec<Stmt>,
+}
+
+/// Builtins and their number of arguments; `len` takes zero or one.
+const BUILTINS: [(&str, usize); 9] = [
+    ("len", 0),
+    ("read", 1),
+    ("write", 2),
+    ("swap", 2),
+    ("compare", 2),
+    ("compare_to", 2),
+    ("phase", 1),
+    ("push", 2),
+    ("pop", 1),
+];
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Blocks and operands being parsed, innermost included.
+    depth: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, Error> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            pos: 0,
+            depth: 0,
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].0.clone();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, Error> {
+        Err(Error {
+            line: self.line(),
+            message,
+        })
+    }
+
+    fn is(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(s) if *s == symbol)
+            || matches!(self.peek(), Token::Ident(s) if s == symbol)
+    }
+
+    /// Consumes `symbol` or keyword if it is next.
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = self.is(symbol);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), Error> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            self.error(format!("expected {symbol}, found {}", self.peek()))
+        }
+    }
+
+    /// Runs `parse` one level deeper, failing past `MAX_NESTING`.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth == MAX_NESTING {
+            return self.error(format!("nested more than {MAX_NESTING} levels deep"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Token::Ident(name) if !KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.next();
+                Ok(name)
+            }
+            token => self.error(format!("expected a name, found {token}")),
+        }
+    }
+
+    fn program(mut self) -> Result<Program, Error> {
+        let mut functions = HashMap::new();
+        let mut main = Vec::new();
+        while *self.peek() != Token::End {
+            if self.is("fn") {
+                let line = self.line();
+                self.next();
+                let name = self.ident()?;
+                self.expect("(")?;
+                let mut params = Vec::new();
+                while !self.eat(")") {
+                    params.push(self.ident()?);
+                    if !self.is(")") {
+                        self.expect(",")?;
+                    }
+                }
+                let body = self.block()?;
+                if BUILTINS.iter().any(|(builtin, _)| *builtin == name)
+                    || functions.contains_key(&name)
+                {
+                    return Err(Error {
+                        line,
+                        message: format!("{name} is already defined"),
+                    });
+                }
+                functions.insert(name, Function { params, body });
+            } else {
+                main.push(self.statement()?);
+            }
+        }
+        Ok(Program {
+            source: String::new(),
+            functions,
+            main,
+        })
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, Error> {
+        self.nested(Self::statements)
+    }
+
+    fn statements(&mut self) -> Result<Vec<Stmt>, Error> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.eat("}") {
+            if *self.peek() == Token::End {
+                return self.error(String::from("expected }"));
+            }
+            stmts.push(self.statement()?);
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, Error> {
+        let line = self.line();
+        let kind = if self.eat("let") {
+            let name = self.ident()?;
+            self.expect("=")?;
+            let value = self.expr()?;
+            self.expect(";")?;
+            Kind::Let(name, value)
+        } else if self.eat("if") {
+            let mut branches = vec![(self.expr()?, self.block()?)];
+            let mut otherwise = Vec::new();
+            while self.eat("else") {
+                if self.eat("if") {
+                    branches.push((self.expr()?, self.block()?));
+                } else {
+                    otherwise = self.block()?;
+                    break;
+                }
+            }
+            Kind::If(branches, otherwise)
+        } else if self.eat("while") {
+            Kind::While(self.expr()?, self.block()?)
+        } else if self.eat("for") {
+            let name = self.ident()?;
+            self.expect("in")?;
+            let start = self.expr()?;
+            self.expect("..")?;
+            let end = self.expr()?;
+            Kind::For(name, start, end, self.block()?)
+        } else if self.eat("return") {
+            let value = if self.is(";") {
+                None
+            } else {
+                Some(self.expr()?)
+            };
+            self.expect(";")?;
+            Kind::Return(value)
+        } else if self.eat("break") {
+            self.expect(";")?;
+            Kind::Break
+        } else if self.eat("continue") {
+            self.expect(";")?;
+            Kind::Continue
+        } else {
+            let expr = self.expr()?;
+            let op = if self.eat("+=") {
+                Some(Op::Add)
+            } else if self.eat("-=") {
+                Some(Op::Sub)
+            } else {
+                None
+            };
+            let kind = if op.is_some() || self.eat("=") {
+                let mut value = self.expr()?;
+                let target = match expr {
+                    Expr::Var(name) => {
+                        if let Some(op) = op {
+                            value =
+                                Expr::Binary(op, Box::new(Expr::Var(name.clone())), value.into());
+                        }
+                        Target::Var(name)
+                    }
+                    Expr::Index(array, index) if op.is_none() => Target::Index(*array, *index),
+                    _ => return self.error(String::from("cannot assign to this expression")),
+                };
+                Kind::Assign(target, value)
+            } else {
+                Kind::Expr(expr)
+            };
+            self.expect(";")?;
+            kind
+        };
+        Ok(Stmt { line, kind })
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        self.binary(0)
+    }
+
+    /// Parses operators of precedence `level` and above.
+    fn binary(&mut self, level: usize) -> Result<Expr, Error> {
+        const LEVELS: [&[(&str, Op)]; 5] = [
+            &[("||", Op::Or)],
+            &[("&&", Op::And)],
+            &[
+                ("==", Op::Eq),
+                ("!=", Op::Ne),
+                ("<=", Op::Le),
+                (">=", Op::Ge),
+                ("<", Op::Lt),
+                (">", Op::Gt),
+            ],
+            &[("+", Op::Add), ("-", Op::Sub)],
+            &[("*", Op::Mul), ("/", Op::Div), ("%", Op::Rem)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, op)) = LEVELS[level].iter().find(|(symbol, _)| self.is(symbol)) {
+            self.next();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.binary(level + 1)?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        self.nested(Self::operand)
+    }
+
+    fn operand(&mut self) -> Result<Expr, Error> {
+        if self.eat("-") {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else if self.eat("!") {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else {
+            let mut expr = self.primary()?;
+            while self.eat("[") {
+                let index = self.expr()?;
+                self.expect("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            }
+            Ok(expr)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Token::Int(n) => Ok(Expr::Int(n)),
+            Token::Str(s) => Ok(Expr::Str(s)),
+            Token::Ident(name) if name == "true" => Ok(Expr::Int(1)),
+            Token::Ident(name) if name == "false" => Ok(Expr::Int(0)),
+            Token::Ident(name) if !KEYWORDS.contains(&name.as_str()) => {
+                if !self.eat("(") {
+                    return Ok(Expr::Var(name));
+                }
+                let mut args = Vec::new();
+                while !self.eat(")") {
+                    args.push(self.expr()?);
+                    if !self.is(")") {
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Token::Symbol("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Symbol("[") => {
+                if self.eat("]") {
+                    return Ok(Expr::Array(Vec::new()));
+                }
+                let first = self.expr()?;
+                if self.eat(";") {
+                    let count = self.expr()?;
+                    self.expect("]")?;
+                    return Ok(Expr::Repeat(Box::new(first), Box::new(count)));
+                }
+                let mut items = vec![first];
+                while self.eat(",") {
+                    items.push(self.expr()?);
+                }
+                self.expect("]")?;
+                Ok(Expr::Array(items))
+            }
+            token => {
+                self.pos -= usize::from(token != Token::End);
+                self.error(format!("expected an expression, found {token}"))
+            }
+        }
+    }
+}
+
+impl Program {
+    /// Reports unknown names, wrong numbers of arguments and misplaced
+    /// `break`s before the script runs.
+    fn check(&self) -> Result<(), Error> {
+        let mut scopes = vec![Vec::new()];
+        self.check_block(&self.main, &mut scopes, false)?;
+        for function in self.functions.values() {
+            let mut scopes = vec![function.params.clone()];
+            self.check_block(&function.body, &mut scopes, false)?;
+        }
+        Ok(())
+    }
+
+    fn check_block(
+        &self,
+        block: &[Stmt],
+        scopes: &mut Vec<Vec<String>>,
+        in_loop: bool,
+    ) -> Result<(), Error> {
+        scopes.push(Vec::new());
+        for stmt in block {
+            let error = |message: String| Error {
+                line: stmt.line,
+                message,
+            };
+            let expr = |expr: &Expr, scopes: &Vec<Vec<String>>| {
+                self.check_expr(expr, scopes).map_err(error)
+            };
+            match &stmt.kind {
+                Kind::Let(name, value) => {
+                    expr(value, scopes)?;
+                    scopes.last_mut().unwrap().push(name.clone());
+                }
+                Kind::Assign(target, value) => {
+                    match target {
+                        Target::Var(name) => expr(&Expr::Var(name.clone()), scopes)?,
+                        Target::Index(array, index) => {
+                            expr(array, scopes)?;
+                            expr(index, scopes)?;
+                        }
+                    }
+                    expr(value, scopes)?;
+                }
+                Kind::Expr(value) => expr(value, scopes)?,
+                Kind::If(branches, otherwise) => {
+                    for (condition, body) in branches {
+                        expr(condition, scopes)?;
+                        self.check_block(body, scopes, in_loop)?;
+                    }
+                    self.check_block(otherwise, scopes, in_loop)?;
+                }
+                Kind::While(condition, body) => {
+                    expr(condition, scopes)?;
+                    self.check_block(body, scopes, true)?;
+                }
+                Kind::For(name, start, end, body) => {
+                    expr(start, scopes)?;
+                    expr(end, scopes)?;
+                    scopes.push(vec![name.clone()]);
+                    let result = self.check_block(body, scopes, true);
+                    scopes.pop();
+                    result?;
+                }
+                Kind::Return(value) => {
+                    if let Some(value) = value {
+                        expr(value, scopes)?;
+                    }
+                }
+                Kind::Break | Kind::Continue if !in_loop => {
+                    return Err(error(String::from("break or continue outside of a loop")));
+                }
+                Kind::Break | Kind::Continue => {}
+            }
+        }
+        scopes.pop();
+        Ok(())
+    }
+
+    fn check_expr(&self, expr: &Expr, scopes: &Vec<Vec<String>>) -> Result<(), String> {
+        match expr {
+            Expr::Int(_) | Expr::Str(_) => Ok(()),
+            Expr::Var(name) => {
+                if scopes.iter().flatten().any(|n| n == name) {
+                    Ok(())
+                } else {
+                    Err(format!("unknown variable {name}"))
+                }
+            }
+            Expr::Array(items) => items.iter().try_for_each(|e| self.check_expr(e, scopes)),
+            Expr::Repeat(a, b) | Expr::Index(a, b) | Expr::Binary(_, a, b) => {
+                self.check_expr(a, scopes)?;
+                self.check_expr(b, scopes)
+            }
+            Expr::Neg(a) | Expr::Not(a) => self.check_expr(a, scopes),
+            Expr::Call(name, args) => {
+                let arity = match (
+                    self.functions.get(name),
+                    BUILTINS.iter().find(|b| b.0 == name),
+                ) {
+                    (Some(function), _) => function.params.len(),
+                    (None, Some(_)) if name == "len" && args.len() == 1 => 1,
+                    (None, Some(&(_, arity))) => arity,
+                    (None, None) => return Err(format!("unknown function {name}")),
+                };
+                if args.len() != arity {
+                    return Err(format!(
+                        "{name} takes {arity} arguments but {} were given",
+                        args.len()
+                    ));
+                }
+                args.iter().try_for_each(|e| self.check_expr(e, scopes))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Value {
+    Int(i64),
+    Str(Rc<str>),
+    Array(Rc<RefCell<Vec<Self>>>),
+    None,
+}
+
+impl Value {
+    /// A length as a number.
+    fn len(len: usize) -> Self {
+        Self::Int(i64::try_from(len).unwrap())
+    }
+
+    const fn kind(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "a number",
+            Self::Str(_) => "a string",
+            Self::Array(_) => "an array",
+            Self::None => "nothing",
+        }
+    }
+}
+
+/// How a statement left the block it is in.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+/// Local variables of a call, innermost block last.
+type Scopes = Vec<HashMap<String, Value>>;
+
+/// Runs a program against an `Interface`.
+struct Machine<'a> {
+    int: &'a Interface,
+    program: &'a Program,
+    /// Length of the array, read once at the start.
+    len: usize,
+    /// Line of the statement being executed, for errors.
+    line: usize,
+    depth: usize,
+}
+
+impl<'a> Machine<'a> {
+    fn new(int: &'a Interface, program: &'a Program) -> Self {
+        Self {
+            int,
+            program,
+            len: int.len(),
+            line: 1,
+            depth: 0,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut scopes = vec![HashMap::new()];
+        self.block(&self.program.main, &mut scopes);
+    }
+
+    /// Ends the run with an error at the current line. The payload is raised
+    /// without the panic hook, so the failure carries no Rust location.
+    fn fail(&self, message: String) -> ! {
+        resume_unwind(Box::new(
+            Error {
+                line: self.line,
+                message,
+            }
+            .to_string(),
+        ));
+    }
+
+    fn block(&mut self, block: &[Stmt], scopes: &mut Scopes) -> Flow {
+        scopes.push(HashMap::new());
+        let mut flow = Flow::Next;
+        for stmt in block {
+            flow = self.statement(stmt, scopes);
+            if !matches!(flow, Flow::Next) {
+                break;
+            }
+        }
+        scopes.pop();
+        flow
+    }
+
+    fn statement(&mut self, stmt: &Stmt, scopes: &mut Scopes) -> Flow {
+        self.line = stmt.line;
+        self.int.line(stmt.line);
+        match &stmt.kind {
+            Kind::Let(name, value) => {
+                let value = self.eval(value, scopes);
+                scopes.last_mut().unwrap().insert(name.clone(), value);
+            }
+            Kind::Assign(Target::Var(name), value) => {
+                let value = self.eval(value, scopes);
+                *scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|scope| scope.get_mut(name))
+                    .unwrap() = value;
+            }
+            Kind::Assign(Target::Index(array, index), value) => {
+                let array = self.array(array, scopes);
+                let index = self.eval(index, scopes);
+                let value = self.eval(value, scopes);
+                let mut items = array.borrow_mut();
+                let i = self.index(&index, items.len());
+                items[i] = value;
+            }
+            Kind::Expr(value) => {
+                self.eval(value, scopes);
+            }
+            Kind::If(branches, otherwise) => {
+                for (condition, body) in branches {
+                    if self.truth(condition, scopes) {
+                        return self.block(body, scopes);
+                    }
+                }
+                return self.block(otherwise, scopes);
+            }
+            Kind::While(condition, body) => {
+                while self.truth(condition, scopes) {
+                    self.int.checkpoint();
+                    match self.block(body, scopes) {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Flow::Return(value),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                    self.line = stmt.line;
+                }
+            }
+            Kind::For(name, start, end, body) => {
+                let start = self.int(start, scopes);
+                let end = self.int(end, scopes);
+                for i in start..end {
+                    self.int.checkpoint();
+                    scopes.push(HashMap::from([(name.clone(), Value::Int(i))]));
+                    let flow = self.block(body, scopes);
+                    scopes.pop();
+                    match flow {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Flow::Return(value),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+            }
+            Kind::Return(value) => {
+                let value = value
+                    .as_ref()
+                    .map_or(Value::None, |value| self.eval(value, scopes));
+                return Flow::Return(value);
+            }
+            Kind::Break => return Flow::Break,
+            Kind::Continue => return Flow::Continue,
+        }
+        Flow::Next
+    }
+
+    fn eval(&mut self, expr: &Expr, scopes: &mut Scopes) -> Value {
+        match expr {
+            Expr::Int(n) => Value::Int(*n),
+            Expr::Str(s) => Value::Str(s.as_str().into()),
+            Expr::Var(name) => scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .cloned()
+                .unwrap(),
+            Expr::Array(items) => {
+                let items = items.iter().map(|e| self.eval(e, scopes)).collect();
+                Value::Array(Rc::new(RefCell::new(items)))
+            }
+            Expr::Repeat(value, count) => {
+                let value = self.eval(value, scopes);
+                let count = self.int(count, scopes);
+                let count = usize::try_from(count)
+                    .unwrap_or_else(|_| self.fail(format!("negative array length {count}")));
+                Value::Array(Rc::new(RefCell::new(vec![value; count])))
+            }
+            Expr::Index(array, index) => {
+                let array = self.array(array, scopes);
+                let index = self.eval(index, scopes);
+                let items = array.borrow();
+                items[self.index(&index, items.len())].clone()
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|e| self.eval(e, scopes)).collect();
+                self.call(name, args)
+            }
+            Expr::Neg(value) => {
+                let n = self.int(value, scopes);
+                Value::Int(n.checked_neg().unwrap_or_else(|| self.overflow()))
+            }
+            Expr::Not(value) => Value::Int(i64::from(!self.truth(value, scopes))),
+            Expr::Binary(Op::And, a, b) => {
+                Value::Int(i64::from(self.truth(a, scopes) && self.truth(b, scopes)))
+            }
+            Expr::Binary(Op::Or, a, b) => {
+                Value::Int(i64::from(self.truth(a, scopes) || self.truth(b, scopes)))
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.eval(a, scopes);
+                let b = self.eval(b, scopes);
+                self.binary(*op, &a, &b)
+            }
+        }
+    }
+
+    fn binary(&self, op: Op, a: &Value, b: &Value) -> Value {
+        let (a, b) = match (a, b) {
+            (Value::Int(a), Value::Int(b)) => (*a, *b),
+            (Value::Str(a), Value::Str(b)) if matches!(op, Op::Eq | Op::Ne) => {
+                return Value::Int(i64::from((a == b) == matches!(op, Op::Eq)));
+            }
+            _ => self.fail(format!(
+                "cannot apply an operator to {} and {}",
+                a.kind(),
+                b.kind()
+            )),
+        };
+        let result = match op {
+            Op::Eq => Some(i64::from(a == b)),
+            Op::Ne => Some(i64::from(a != b)),
+            Op::Lt => Some(i64::from(a < b)),
+            Op::Le => Some(i64::from(a <= b)),
+            Op::Gt => Some(i64::from(a > b)),
+            Op::Ge => Some(i64::from(a >= b)),
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div | Op::Rem if b == 0 => self.fail(String::from("division by zero")),
+            Op::Div => a.checked_div(b),
+            Op::Rem => a.checked_rem(b),
+            Op::And | Op::Or => unreachable!("short-circuited in eval"),
+        };
+        Value::Int(result.unwrap_or_else(|| self.overflow()))
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>) -> Value {
+        if let Some(function) = self.program.functions.get(name) {
+            if self.depth == MAX_DEPTH {
+                self.fail(format!("more than {MAX_DEPTH} nested calls"));
+            }
+            self.int.checkpoint();
+            let mut scopes = vec![function.params.iter().cloned().zip(args).collect()];
+            let line = self.line;
+            self.depth += 1;
+            let flow = self.block(&function.body, &mut scopes);
+            self.depth -= 1;
+            self.line = line;
+            return match flow {
+                Flow::Return(value) => value,
+                _ => Value::None,
+            };
+        }
+
+        let int = self.int;
+        let ordering = |ordering: std::cmp::Ordering| Value::Int(i64::from(ordering as i8));
+        match (name, &args[..]) {
+            ("len", []) => Value::len(self.len),
+            ("len", [Value::Array(items)]) => Value::len(items.borrow().len()),
+            ("len", [Value::Str(s)]) => Value::len(s.len()),
+            ("read", [i]) => Value::Int(i64::from(int.read(self.index(i, self.len)))),
+            ("write", [i, value]) => {
+                let i = self.index(i, self.len);
+                int.write(i, self.element(value));
+                Value::None
+            }
+            ("swap", [i, j]) => {
+                int.swap(self.index(i, self.len), self.index(j, self.len));
+                Value::None
+            }
+            ("compare", [i, j]) => {
+                ordering(int.compare(self.index(i, self.len), self.index(j, self.len)))
+            }
+            ("compare_to", [i, value]) => {
+                ordering(int.compare_to(self.index(i, self.len), self.element(value)))
+            }
+            ("phase", [Value::Str(label)]) => {
+                int.phase(label);
+                Value::None
+            }
+            ("push", [Value::Array(items), value]) => {
+                items.borrow_mut().push(value.clone());
+                Value::None
+            }
+            ("pop", [Value::Array(items)]) => items
+                .borrow_mut()
+                .pop()
+                .unwrap_or_else(|| self.fail(String::from("pop from an empty array"))),
+            _ => self.fail(format!(
+                "wrong arguments for {name}: {}",
+                args.iter().map(Value::kind).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+
+    fn truth(&mut self, expr: &Expr, scopes: &mut Scopes) -> bool {
+        self.int(expr, scopes) != 0
+    }
+
+    fn int(&mut self, expr: &Expr, scopes: &mut Scopes) -> i64 {
+        match self.eval(expr, scopes) {
+            Value::Int(n) => n,
+            value => self.fail(format!("expected a number, found {}", value.kind())),
+        }
+    }
+
+    fn array(&mut self, expr: &Expr, scopes: &mut Scopes) -> Rc<RefCell<Vec<Value>>> {
+        match self.eval(expr, scopes) {
+            Value::Array(items) => items,
+            value => self.fail(format!("expected an array, found {}", value.kind())),
+        }
+    }
+
+    /// `value` as an index into something of length `len`.
+    fn index(&self, value: &Value, len: usize) -> usize {
+        let Value::Int(i) = *value else {
+            self.fail(format!("expected an index, found {}", value.kind()));
+        };
+        usize::try_from(i)
+            .ok()
+            .filter(|&i| i < len)
+            .unwrap_or_else(|| self.fail(format!("index {i} out of bounds for length {len}")))
+    }
+
+    /// `value` as an element of the array.
+    fn element(&self, value: &Value) -> u32 {
+        let Value::Int(v) = *value else {
+            self.fail(format!("expected a number, found {}", value.kind()));
+        };
+        u32::try_from(v).unwrap_or_else(|_| self.fail(format!("{v} does not fit in the array")))
+    }
+
+    fn overflow(&self) -> ! {
+        self.fail(String::from("arithmetic overflow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sorter::Sorter;
+
+    /// `expr` with every operation in parentheses.
+    fn show(expr: &Expr) -> String {
+        match expr {
+            Expr::Int(n) => n.to_string(),
+            Expr::Var(name) => name.clone(),
+            Expr::Neg(a) => format!("(-{})", show(a)),
+            Expr::Not(a) => format!("(!{})", show(a)),
+            Expr::Index(a, i) => format!("{}[{}]", show(a), show(i)),
+            Expr::Binary(op, a, b) => {
+                let symbol = match op {
+                    Op::Or => "||",
+                    Op::And => "&&",
+                    Op::Eq => "==",
+                    Op::Ne => "!=",
+                    Op::Lt => "<",
+                    Op::Le => "<=",
+                    Op::Gt => ">",
+                    Op::Ge => ">=",
+                    Op::Add => "+",
+                    Op::Sub => "-",
+                    Op::Mul => "*",
+                    Op::Div => "/",
+                    Op::Rem => "%",
+                };
+                format!("({} {symbol} {})", show(a), show(b))
+            }
+            Expr::Str(_) | Expr::Array(_) | Expr::Repeat(..) | Expr::Call(..) => {
+                unimplemented!()
+            }
+        }
+    }
+
+    fn parse(source: &str) -> String {
+        show(&Parser::new(source).unwrap().expr().unwrap())
+    }
+
+    fn compile(source: &str) -> Result<Program, Error> {
+        let program = Parser::new(source)?.program()?;
+        program.check()?;
+        Ok(program)
+    }
+
+    fn error(source: &str) -> String {
+        compile(source).err().unwrap().to_string()
+    }
+
+    /// Runs `source` as the loaded script over `data`, returning the array and
+    /// the failure, if any. Scripts share `PROGRAM`, so they run one at a time.
+    fn run(source: &str, data: Vec<u32>) -> (Vec<u32>, Option<String>) {
+        static LOCK: Mutex<()> = Mutex::new(());
+        let _lock = LOCK.lock().unwrap();
+        load(source).unwrap();
+        let mut sorter = Sorter::new(data);
+        sorter.method = Some(&METHOD);
+        sorter.throttled = false;
+        sorter.start(false);
+        sorter.wait();
+        let mut state = sorter.state.lock().unwrap();
+        let failure = state.failure.take().map(|failure| failure.message);
+        (state.data.clone(), failure)
+    }
+
+    #[test]
+    fn tokenizer_errors() {
+        assert_eq!(error("let s = \"open;"), "line 1: unterminated string");
+        assert_eq!(
+            error("\nlet x = 1 # 2;"),
+            "line 2: unexpected character '#'"
+        );
+        assert_eq!(
+            error("let x = 99999999999999999999;"),
+            "line 1: number too large: 99999999999999999999"
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(parse("1 + 2 * 3 - 4"), "((1 + (2 * 3)) - 4)");
+        assert_eq!(parse("a || b && c == d"), "(a || (b && (c == d)))");
+        assert_eq!(parse("a < b + 1 != c % 2"), "((a < (b + 1)) != (c % 2))");
+        assert_eq!(parse("-a[i] * (b - c)"), "((-a[i]) * (b - c))");
+        assert_eq!(parse("!a && !!b"), "((!a) && (!(!b)))");
+    }
+
+    #[test]
+    fn checker_rejects() {
+        assert_eq!(error("let x = y;"), "line 1: unknown variable y");
+        assert_eq!(error("\nsort(1);"), "line 2: unknown function sort");
+        assert_eq!(
+            error("swap(1);"),
+            "line 1: swap takes 2 arguments but 1 were given"
+        );
+        assert_eq!(
+            error("fn f(a, b) { return a; }\nf(1);"),
+            "line 2: f takes 2 arguments but 1 were given"
+        );
+        assert_eq!(
+            error("break;"),
+            "line 1: break or continue outside of a loop"
+        );
+        assert_eq!(error("fn read(i) {}"), "line 1: read is already defined");
+        assert_eq!(
+            error("for i in 0..3 {}\nlet x = i;"),
+            "line 2: unknown variable i"
+        );
+        assert!(compile("let a = [0; 3]; a[1] = len(a); let n = len();").is_ok());
+    }
+
+    #[test]
+    fn nesting_is_bounded() {
+        let deep = format!("let x = {}1{};", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(error(&deep), "line 1: nested more than 64 levels deep");
+        let negated = format!("let x = {}1;", "-".repeat(100_000));
+        assert_eq!(error(&negated), "line 1: nested more than 64 levels deep");
+        let blocks = format!("{}{}", "if 1 {".repeat(100_000), "}".repeat(100_000));
+        assert_eq!(error(&blocks), "line 1: nested more than 64 levels deep");
+        let shallow = format!("let x = {}1{};", "(".repeat(50), ")".repeat(50));
+        assert!(compile(&shallow).is_ok());
+    }
+
+    #[test]
+    fn recursion_is_bounded() {
+        let (_, failure) = run("fn f(n) { return f(n + 1); }\nf(0);", vec![1]);
+        assert_eq!(
+            failure.as_deref(),
+            Some("line 1: more than 10000 nested calls")
+        );
+    }
+
+    #[test]
+    fn sorts() {
+        let (data, failure) = run(EXAMPLE, vec![5, 1, 4, 2, 3, 2]);
+        assert!(failure.is_none());
+        assert_eq!(data, [1, 2, 2, 3, 4, 5]);
+
+        let selection = "\
+fn smallest(from) {
+    let k = from;
+    for i in from + 1..len() {
+        if compare(i, k) < 0 {
+            k = i;
+        }
+    }
+    return k;
+}
+for i in 0..len() {
+    swap(i, smallest(i));
+}
+";
+        let (data, failure) = run(selection, vec![3, 9, 1, 7, 1]);
+        assert!(failure.is_none());
+        assert_eq!(data, [1, 1, 3, 7, 9]);
+    }
+
+    #[test]
+    fn runtime_errors() {
+        let (data, failure) = run("swap(0, 5);", vec![2, 1]);
+        assert_eq!(
+            failure.as_deref(),
+            Some("line 1: index 5 out of bounds for length 2")
+        );
+        assert_eq!(data, [2, 1]);
+        let (_, failure) = run("let x = 1 / (len() - 2);", vec![2, 1]);
+        assert_eq!(failure.as_deref(), Some("line 1: division by zero"));
+    }
+}
@@ -0,0 +1,730 @@
+use crate::json::quote;
+use crate::methods::{METHODS, MODIFIERS};
+use crate::sorter::{Method, State, Step};
+use crate::{Speed, GLOBAL_STATE};
+use eframe::egui;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Appended to a client's key to accept a WebSocket handshake (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Steps buffered for a WebSocket client; steps beyond it are dropped.
+const STEP_BUFFER: usize = 4096;
+/// Time between array snapshots sent to WebSocket clients.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(100);
+/// Largest array sent in a snapshot; bigger ones are sent without their data.
+const MAX_SNAPSHOT: usize = 100_000;
+/// Largest message accepted from a WebSocket client, fragments included.
+const MAX_MESSAGE: u64 = 1 << 16;
+/// How long a request waits for the window to carry it out.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something a remote client asks the window to do, as if through its controls.
+#[derive(Clone, Copy)]
+pub enum Action {
+    Generate(u32),
+    Modify(&'static Method),
+    /// Start the given method, or the selected one.
+    Start(Option<&'static Method>),
+    Stop,
+    Pause,
+    Resume,
+    Step,
+}
+
+/// An `Action` and where to send whether it was carried out.
+pub struct Request {
+    pub action: Action,
+    pub reply: Sender<Result<(), String>>,
+}
+
+/// Serves the remote-control API on `port` of the loopback interface.
+///
+/// Requests that change the run are passed to the window through the returned
+/// receiver; `ctx` is woken up for each. Requests from browsers are refused
+/// unless the page comes from localhost.
+pub fn start(
+    port: u16,
+    state: Arc<Mutex<State>>,
+    ctx: egui::Context,
+) -> io::Result<Receiver<Request>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let (requests, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (requests, state, ctx) = (requests.clone(), state.clone(), ctx.clone());
+            thread::spawn(move || {
+                // A client that hangs up early is none of our concern.
+                let _ = serve(stream, &requests, &state, &ctx);
+            });
+        }
+    });
+    Ok(receiver)
+}
+
+/// A parsed HTTP request line and headers.
+struct Http {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Http> {
+    let mut first = String::new();
+    reader.read_line(&mut first)?;
+    let mut parts = first.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+
+    let mut headers = HashMap::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_owned());
+        }
+    }
+    Ok(Http {
+        method,
+        path: path.to_owned(),
+        query,
+        headers,
+    })
+}
+
+/// `text` with `+` and `%XX` escapes of a query string decoded. Malformed
+/// escapes are kept as they are.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = text
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(|hex| u8::from_str_radix(hex, 16).unwrap());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, None) => {
+                out.push(if byte == b'+' { b' ' } else { byte });
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn serve(
+    stream: TcpStream,
+    requests: &Sender<Request>,
+    state: &Mutex<State>,
+    ctx: &egui::Context,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = read_request(&mut reader)?;
+    let mut stream = stream;
+    let local = request
+        .headers
+        .get("origin")
+        .is_none_or(|o| local_origin(o));
+    let (status, body) = match (request.method.as_str(), request.path.as_str()) {
+        // Browsers send any page's requests here; only local pages may drive it.
+        _ if !local => (403, error("origin not allowed")),
+        ("GET", "/ws") => return websocket(stream, &request, state),
+        ("GET", "/methods") => (200, methods()),
+        ("GET", "/state") => (200, status(state)),
+        ("POST", "/speed") => match speed(&request.query) {
+            Ok(()) => (200, String::from("{\"ok\": true}")),
+            Err(err) => (400, error(&err)),
+        },
+        ("POST", _) => match action(&request) {
+            Ok(Some(action)) => perform(action, requests, ctx),
+            Ok(None) => (404, error("no such endpoint")),
+            Err(err) => (400, error(&err)),
+        },
+        (
+            _,
+            "/ws" | "/methods" | "/state" | "/speed" | "/generate" | "/modify" | "/start" | "/stop"
+            | "/pause" | "/resume" | "/step",
+        ) => (405, error("method not allowed")),
+        _ => (404, error("no such endpoint")),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Whether `origin` is a page served from this machine.
+fn local_origin(origin: &str) -> bool {
+    let Some((_, host)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match host.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').map_or(rest, |(host, _)| host),
+        None => host.split_once(':').map_or(host, |(host, _)| host),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// The `Action` a POST request asks for, if it names one.
+fn action(request: &Http) -> Result<Option<Action>, String> {
+    let param = |name: &str| {
+        request
+            .query
+            .get(name)
+            .ok_or_else(|| format!("missing parameter {name}"))
+    };
+    let find = |methods: &'static [Method], name: &str| {
+        methods
+            .iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| format!("unknown method: {name}"))
+    };
+    Ok(Some(match request.path.as_str() {
+        "/generate" => {
+            let size = param("size")?;
+            match size.parse() {
+                Ok(n) if n > 0 => Action::Generate(n),
+                _ => return Err(format!("invalid size: {size}")),
+            }
+        }
+        "/modify" => Action::Modify(find(MODIFIERS, param("name")?)?),
+        "/start" => Action::Start(match request.query.get("method") {
+            Some(name) => Some(find(METHODS, name)?),
+            None => None,
+        }),
+        "/stop" => Action::Stop,
+        "/pause" => Action::Pause,
+        "/resume" => Action::Resume,
+        "/step" => Action::Step,
+        _ => return Ok(None),
+    }))
+}
+
+/// Passes `action` to the window and waits for it to be carried out.
+fn perform(action: Action, requests: &Sender<Request>, ctx: &egui::Context) -> (u16, String) {
+    let (reply, outcome) = mpsc::channel();
+    if requests.send(Request { action, reply }).is_err() {
+        return (503, error("the window has closed"));
+    }
+    ctx.request_repaint();
+    match outcome.recv_timeout(REPLY_TIMEOUT) {
+        Ok(Ok(())) => (200, String::from("{\"ok\": true}")),
+        Ok(Err(err)) => (400, error(&err)),
+        Err(_) => (503, error("the window did not respond")),
+    }
+}
+
+/// Sets the delay or the operations per frame, whichever the query names.
+fn speed(query: &HashMap<String, String>) -> Result<(), String> {
+    let mut global = GLOBAL_STATE.lock().unwrap();
+    if let Some(delay) = query.get("delay") {
+        global.delay = delay
+            .parse()
+            .map_err(|_| format!("invalid delay: {delay}"))?;
+        global.speed = Speed::Delay;
+    } else if let Some(ops) = query.get("ops-per-frame") {
+        global.ops_per_frame = ops
+            .parse()
+            .ok()
+            .filter(|ops: &f64| ops.is_finite() && *ops > 0.0)
+            .ok_or_else(|| format!("invalid ops-per-frame: {ops}"))?;
+        global.speed = Speed::PerFrame;
+    } else {
+        return Err(String::from("missing parameter delay or ops-per-frame"));
+    }
+    Ok(())
+}
+
+fn methods() -> String {
+    let list = |methods: &[Method]| {
+        methods
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{\"name\": {}, \"stable\": {}, \"in_place\": {}, \"adaptive\": {}}}",
+                    quote(m.name),
+                    m.info.stable,
+                    m.info.in_place,
+                    m.info.adaptive
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!(
+        "{{\"methods\": [{}], \"modifiers\": [{}]}}",
+        list(METHODS),
+        list(MODIFIERS)
+    )
+}
+
+fn status(state: &Mutex<State>) -> String {
+    let global = GLOBAL_STATE.lock().unwrap();
+    let (paused, delay, ops_per_frame) = (global.paused, global.delay, global.ops_per_frame);
+    let speed = match global.speed {
+        Speed::Delay => "delay",
+        Speed::PerFrame => "ops-per-frame",
+    };
+    drop(global);
+    let state = state.lock().unwrap();
+    format!(
+        "{{\"sorting\": {}, \"paused\": {paused}, \"ops\": {}, \"size\": {}, \"phase\": {}, \
+         \"speed\": \"{speed}\", \"delay\": {delay}, \"ops_per_frame\": {ops_per_frame}}}",
+        state.sorting,
+        state.ops,
+        state.data.len(),
+        state
+            .phase
+            .as_deref()
+            .map_or_else(|| String::from("null"), quote)
+    )
+}
+
+fn error(message: &str) -> String {
+    format!("{{\"error\": {}}}", quote(message))
+}
+
+/// What a WebSocket client sent that the server must answer.
+enum Incoming {
+    Ping(Vec<u8>),
+    Close,
+}
+
+/// Upgrades the connection and streams steps and array snapshots until the
+/// client leaves.
+fn websocket(mut stream: TcpStream, request: &Http, state: &Mutex<State>) -> io::Result<()> {
+    let Some(key) = request.headers.get("sec-websocket-key") else {
+        let body = error("expected a WebSocket handshake");
+        return write!(
+            stream,
+            "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+    };
+    let accept = base64(&sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes()));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {accept}\r\n\r\n"
+    )?;
+
+    let (tap, steps) = mpsc::sync_channel(STEP_BUFFER);
+    state.lock().unwrap().taps.push(tap);
+    let (incoming, messages) = mpsc::channel();
+    let mut reader = Messages {
+        stream: stream.try_clone()?,
+        partial: None,
+    };
+    thread::spawn(move || loop {
+        let message = match reader.next() {
+            Ok((0x9, payload)) => Incoming::Ping(payload),
+            Ok((0x8, _)) | Err(_) => Incoming::Close,
+            Ok(_) => continue,
+        };
+        let close = matches!(message, Incoming::Close);
+        if incoming.send(message).is_err() || close {
+            return;
+        }
+    });
+
+    let mut last = None;
+    let mut snapshot_time = Instant::now()
+        .checked_sub(SNAPSHOT_INTERVAL)
+        .unwrap_or_else(Instant::now);
+    loop {
+        let mut batch = Vec::new();
+        match steps.recv_timeout(SNAPSHOT_INTERVAL) {
+            Ok(step) => batch.push(step),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        batch.extend(steps.try_iter().take(STEP_BUFFER));
+        if !batch.is_empty() {
+            let steps = batch
+                .iter()
+                .map(|(op, step)| step_json(*op, step))
+                .collect::<Vec<_>>();
+            let message = format!("{{\"type\": \"steps\", \"steps\": [{}]}}", steps.join(", "));
+            send(&mut stream, 0x1, message.as_bytes())?;
+        }
+
+        if snapshot_time.elapsed() >= SNAPSHOT_INTERVAL {
+            snapshot_time = Instant::now();
+            let state = state.lock().unwrap();
+            let data = (state.data.len() <= MAX_SNAPSHOT).then(|| state.data.clone());
+            let current = (state.sorting, state.ops, data);
+            drop(state);
+            if last.as_ref() != Some(&current) {
+                send(&mut stream, 0x1, snapshot(&current).as_bytes())?;
+                last = Some(current);
+            }
+        }
+
+        for message in messages.try_iter() {
+            match message {
+                Incoming::Ping(payload) => send(&mut stream, 0xA, &payload)?,
+                Incoming::Close => {
+                    // The client may already be gone.
+                    let _ = send(&mut stream, 0x8, &[]);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn step_json(op: u64, step: &Step) -> String {
+    match *step {
+        Step::Read(i) => format!("{{\"op\": {op}, \"kind\": \"read\", \"i\": {i}}}"),
+        Step::Compare(i, j) => {
+            format!("{{\"op\": {op}, \"kind\": \"compare\", \"i\": {i}, \"j\": {j}}}")
+        }
+        Step::Swap(i, j) => format!("{{\"op\": {op}, \"kind\": \"swap\", \"i\": {i}, \"j\": {j}}}"),
+        Step::Write(i) => format!("{{\"op\": {op}, \"kind\": \"write\", \"i\": {i}}}"),
+    }
+}
+
+fn snapshot((sorting, ops, data): &(bool, u64, Option<Vec<u32>>)) -> String {
+    let data = data.as_ref().map_or_else(
+        || String::from("null"),
+        |data| {
+            let values = data.iter().map(u32::to_string).collect::<Vec<_>>();
+            format!("[{}]", values.join(","))
+        },
+    );
+    format!("{{\"type\": \"snapshot\", \"sorting\": {sorting}, \"ops\": {ops}, \"data\": {data}}}")
+}
+
+/// Writes an unmasked frame, as servers do.
+fn send(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(u8::try_from(len).unwrap()),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&u16::try_from(len).unwrap().to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+/// Reads a frame, returning whether it is the final fragment of its message,
+/// its opcode and its unmasked payload. Clients must mask their frames.
+fn frame(stream: &mut impl Read) -> io::Result<(bool, u8, Vec<u8>)> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut head = [0; 2];
+    stream.read_exact(&mut head)?;
+    if head[1] & 0x80 == 0 {
+        return Err(invalid("unmasked frame"));
+    }
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            stream.read_exact(&mut len)?;
+            u64::from(u16::from_be_bytes(len))
+        }
+        127 => {
+            let mut len = [0; 8];
+            stream.read_exact(&mut len)?;
+            u64::from_be_bytes(len)
+        }
+        len => u64::from(len),
+    };
+    if len > MAX_MESSAGE {
+        return Err(invalid("frame too large"));
+    }
+    let mut mask = [0; 4];
+    stream.read_exact(&mut mask)?;
+    let mut payload = vec![0; usize::try_from(len).unwrap()];
+    stream.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((head[0] & 0x80 != 0, head[0] & 0x0F, payload))
+}
+
+/// The messages a WebSocket client sends, with fragmented ones joined.
+struct Messages<R> {
+    stream: R,
+    /// Opcode and payload so far of a message still missing fragments.
+    partial: Option<(u8, Vec<u8>)>,
+}
+
+impl<R: Read> Messages<R> {
+    /// Reads the next message, returning its opcode and payload. Control
+    /// frames are returned as they come, even between fragments.
+    fn next(&mut self) -> io::Result<(u8, Vec<u8>)> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        loop {
+            let (fin, opcode, payload) = frame(&mut self.stream)?;
+            if opcode & 0x8 != 0 {
+                if !fin {
+                    return Err(invalid("fragmented control frame"));
+                }
+                return Ok((opcode, payload));
+            }
+            let (opcode, mut message) = match (opcode, self.partial.take()) {
+                (0, Some(partial)) => partial,
+                (0, None) => return Err(invalid("continuation of no message")),
+                (_, Some(_)) => return Err(invalid("message interrupted by another")),
+                (opcode, None) => (opcode, Vec::new()),
+            };
+            message.extend_from_slice(&payload);
+            if message.len() as u64 > MAX_MESSAGE {
+                return Err(invalid("message too large"));
+            }
+            if fin {
+                return Ok((opcode, message));
+            }
+            self.partial = Some((opcode, message));
+        }
+    }
+}
+
+/// SHA-1 of `data` (FIPS 180-4), needed only for the WebSocket handshake.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut hash: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut schedule = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            schedule[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..80 {
+            schedule[i] = (schedule[i - 3] ^ schedule[i - 8] ^ schedule[i - 14] ^ schedule[i - 16])
+                .rotate_left(1);
+        }
+        // The working variables a to e of the standard.
+        let mut v = hash;
+        for (i, &word) in schedule.iter().enumerate() {
+            let (mix, constant) = match i {
+                0..=19 => ((v[1] & v[2]) | (!v[1] & v[3]), 0x5A82_7999),
+                20..=39 => (v[1] ^ v[2] ^ v[3], 0x6ED9_EBA1),
+                40..=59 => ((v[1] & v[2]) | (v[1] & v[3]) | (v[2] & v[3]), 0x8F1B_BCDC),
+                _ => (v[1] ^ v[2] ^ v[3], 0xCA62_C1D6),
+            };
+            let temp = v[0]
+                .rotate_left(5)
+                .wrapping_add(mix)
+                .wrapping_add(v[4])
+                .wrapping_add(constant)
+                .wrapping_add(word);
+            v = [temp, v[0], v[1].rotate_left(30), v[2], v[3]];
+        }
+        for (total, value) in hash.iter_mut().zip(v) {
+            *total = total.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(hash) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for k in 0..4 {
+            if k <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * k) & 0x3F) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write as _;
+
+    /// A client frame carrying `payload` masked with `mask`.
+    fn masked(fin: bool, opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+        let mut frame = vec![
+            u8::from(fin) << 7 | opcode,
+            0x80 | u8::try_from(payload.len()).unwrap(),
+        ];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+        frame
+    }
+
+    fn messages(bytes: Vec<u8>) -> Messages<io::Cursor<Vec<u8>>> {
+        Messages {
+            stream: io::Cursor::new(bytes),
+            partial: None,
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        // RFC 6455, section 1.3.
+        let key = "dGhlIHNhbXBsZSBub25jZQ==";
+        let accept = base64(&sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes()));
+        assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn sha1_and_base64() {
+        let hex = |digest: [u8; 20]| {
+            digest.iter().fold(String::new(), |mut hex, b| {
+                write!(hex, "{b:02x}").unwrap();
+                hex
+            })
+        };
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(hex(sha1(long)), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b""), "");
+    }
+
+    #[test]
+    fn origins() {
+        for origin in [
+            "http://localhost",
+            "http://localhost:8080",
+            "https://127.0.0.1:3000",
+            "http://[::1]:8080",
+        ] {
+            assert!(local_origin(origin), "{origin}");
+        }
+        for origin in [
+            "http://example.com",
+            "http://localhost.example.com",
+            "http://127.0.0.1.example.com:80",
+            "http://[::2]",
+            "null",
+            "localhost",
+        ] {
+            assert!(!local_origin(origin), "{origin}");
+        }
+    }
+
+    #[test]
+    fn requests() {
+        let raw = "POST /start?method=parallel%20quick&x=a+b%2&y HTTP/1.1\r\n\
+                   Host: localhost\r\nOrigin: http://localhost:8000\r\n\r\n";
+        let request = read_request(&mut io::Cursor::new(raw)).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/start");
+        assert_eq!(request.query["method"], "parallel quick");
+        assert_eq!(request.query["x"], "a b%2");
+        assert_eq!(request.query["y"], "");
+        assert_eq!(request.headers["origin"], "http://localhost:8000");
+        assert_eq!(decode("%e2%9C%93%zz%"), "\u{2713}%zz%");
+    }
+
+    #[test]
+    fn masked_frames() {
+        // RFC 6455, section 5.7: a masked "Hello".
+        let hello = vec![
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        assert_eq!(messages(hello).next().unwrap(), (0x1, b"Hello".to_vec()));
+
+        let unmasked = vec![0x81, 0x05, b'H', b'e', b'l', b'l', b'o'];
+        assert!(messages(unmasked).next().is_err());
+    }
+
+    #[test]
+    fn fragmented_frames() {
+        let mask = [1, 2, 3, 4];
+        let mut bytes = masked(false, 0x1, b"Hel", mask);
+        bytes.extend(masked(true, 0x9, b"ping", mask));
+        bytes.extend(masked(false, 0x0, b"l", mask));
+        bytes.extend(masked(true, 0x0, b"o", mask));
+        bytes.extend(masked(true, 0x8, b"", mask));
+        let mut reader = messages(bytes);
+        assert_eq!(reader.next().unwrap(), (0x9, b"ping".to_vec()));
+        assert_eq!(reader.next().unwrap(), (0x1, b"Hello".to_vec()));
+        assert_eq!(reader.next().unwrap(), (0x8, Vec::new()));
+        assert!(reader.next().is_err());
+
+        let stray = masked(true, 0x0, b"lo", mask);
+        assert!(messages(stray).next().is_err());
+    }
+
+    #[test]
+    fn server_frames() {
+        let mut out = Vec::new();
+        send(&mut out, 0x1, b"hi").unwrap();
+        assert_eq!(out, [0x81, 2, b'h', b'i']);
+        out.clear();
+        send(&mut out, 0x2, &[7; 300]).unwrap();
+        assert_eq!(out[..4], [0x82, 126, 1, 44]);
+        assert_eq!(out.len(), 4 + 300);
+    }
+}
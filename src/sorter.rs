@@ -1,104 +1,925 @@
-use crate::GLOBAL_STATE;
-use std::panic::{panic_any, set_hook};
-use std::sync::{Arc, Mutex, Weak};
+use crate::cache::{self, Cache};
+use crate::comparator::{Comparator, Judge};
+use crate::cost::Costs;
+use crate::debugger::{Breakpoint, Change};
+use crate::export::Recorder;
+use crate::external::{self, Disk};
+#[cfg(feature = "fallible")]
+use crate::fault::{Fault, Injector};
+use crate::trace::Tracer;
+use crate::{Speed, GLOBAL_STATE};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, resume_unwind, set_hook, take_hook, AssertUnwindSafe};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, Once};
 use std::thread;
-use std::thread::{spawn, JoinHandle};
-use std::time::Instant;
+use std::thread::{JoinHandle, Scope, ScopedJoinHandle, Thread};
+use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 pub enum Step {
     Read(usize),
+    Compare(usize, usize),
     Swap(usize, usize),
+    Write(usize),
 }
 
+/// The thread behind one cursor of a run and the last operation it performed.
+///
+/// Sequential methods have a single cursor; `Interface::join` and
+/// `Interface::parallel` add one per extra thread.
+#[derive(Clone)]
+pub struct Cursor {
+    pub step: Option<Step>,
+    thread: Thread,
+}
+
+/// A handle on the array for one thread of a method. Further threads get their
+/// own through `join` and `parallel`.
 pub struct Interface {
-    state: Weak<Mutex<State>>,
+    state: Arc<Mutex<State>>,
+    cancel: Arc<AtomicBool>,
+    /// Index of this interface's cursor in `State::cursors`.
+    cursor: usize,
+    line: Cell<Option<usize>>,
+    throttled: bool,
+    judge: Arc<Judge>,
+    budget: Option<u64>,
+    deadline: Option<Instant>,
+    costs: Costs,
+    trace: Option<Arc<Tracer>>,
+    #[cfg(feature = "fallible")]
+    injector: Option<Arc<Injector>>,
+}
+
+/// Unwinding payload used to leave a method once its run has been cancelled.
+struct Cancelled;
+
+/// A payload caught from a thread of a method, with the panic location if any.
+type Unwound = (Box<dyn Any + Send>, Option<String>);
+
+/// Why a run was aborted by its `Limits`; also the unwinding payload used to
+/// leave the method.
+#[derive(Clone, Copy)]
+pub enum Abort {
+    Budget,
+    Timeout,
+}
+
+impl std::fmt::Display for Abort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Budget => "budget exceeded",
+            Self::Timeout => "timed out",
+        })
+    }
 }
 
-struct StopThread;
+/// Bounds on a single run.
+#[derive(Clone, Copy, Default)]
+pub struct Limits {
+    /// Maximum number of operations.
+    pub budget: Option<u64>,
+    /// Maximum wall-clock time, pauses included.
+    pub timeout: Option<Duration>,
+}
+
+/// Operations performed in a run, by kind.
+#[derive(Clone, Copy, Default)]
+pub struct Counters {
+    pub reads: u64,
+    pub compares: u64,
+    pub swaps: u64,
+    pub writes: u64,
+}
 
 impl Interface {
-    pub const fn new(state: Weak<Mutex<State>>) -> Self {
-        Self { state }
+    /// An interface for the current thread, which becomes a cursor of `state`.
+    pub fn new(state: Arc<Mutex<State>>, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            state,
+            cancel,
+            cursor: 0,
+            line: Cell::new(None),
+            throttled: true,
+            judge: Arc::new(Judge::new(Comparator::Consistent, 0)),
+            budget: None,
+            deadline: None,
+            costs: Costs::default(),
+            trace: None,
+            #[cfg(feature = "fallible")]
+            injector: None,
+        }
+        .attach()
     }
 
-    fn modify_state<F, T>(&self, f: F) -> T
+    /// An interface that ignores pausing and the speed setting.
+    pub fn unthrottled(state: Arc<Mutex<State>>, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            throttled: false,
+            ..Self::new(state, cancel)
+        }
+    }
+
+    /// Answers comparisons with `comparator`; `after` is the number of truthful
+    /// answers for `Comparator::LiesAfter`.
+    pub fn with_comparator(self, comparator: Comparator, after: u64) -> Self {
+        Self {
+            judge: Arc::new(Judge::new(comparator, after)),
+            ..self
+        }
+    }
+
+    /// Aborts the method once it exceeds `limits`, counted from now.
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self {
+            budget: limits.budget,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+            ..self
+        }
+    }
+
+    /// Throttles each operation in proportion to its cost under `costs`.
+    pub fn with_costs(self, costs: Costs) -> Self {
+        Self { costs, ..self }
+    }
+
+    /// Writes every operation of this interface to `tracer`.
+    pub fn with_trace(self, tracer: Arc<Tracer>) -> Self {
+        Self {
+            trace: Some(tracer),
+            ..self
+        }
+    }
+
+    /// Makes the operations of this interface faulty.
+    #[cfg(feature = "fallible")]
+    pub fn with_injector(self, injector: Injector) -> Self {
+        Self {
+            injector: Some(Arc::new(injector)),
+            ..self
+        }
+    }
+
+    /// Registers the current thread as a new cursor of the run.
+    fn attach(mut self) -> Self {
+        let mut state = self.state.lock().unwrap();
+        let cursor = Some(Cursor {
+            step: None,
+            thread: thread::current(),
+        });
+        if let Some(free) = state.cursors.iter().position(Option::is_none) {
+            state.cursors[free] = cursor;
+            self.cursor = free;
+        } else {
+            state.cursors.push(cursor);
+            self.cursor = state.cursors.len() - 1;
+        }
+        drop(state);
+        self
+    }
+
+    /// Removes this interface's cursor once its thread is done.
+    fn detach(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(cursor) = state.cursors.get_mut(self.cursor) {
+            *cursor = None;
+        }
+        while state.cursors.last().is_some_and(Option::is_none) {
+            state.cursors.pop();
+        }
+        drop(state);
+    }
+
+    /// A copy of this interface for another thread; it still has to `attach`.
+    fn fork(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            cancel: self.cancel.clone(),
+            cursor: 0,
+            line: Cell::new(self.line.get()),
+            throttled: self.throttled,
+            judge: self.judge.clone(),
+            budget: self.budget,
+            deadline: self.deadline,
+            costs: self.costs,
+            trace: self.trace.clone(),
+            #[cfg(feature = "fallible")]
+            injector: self.injector.clone(),
+        }
+    }
+
+    /// Runs `a` on this cursor and `b` on a new thread with a cursor of its own,
+    /// returning once both have finished.
+    pub fn join<A, B>(&self, a: A, b: B)
     where
-        F: FnOnce(&mut State) -> T,
+        A: FnOnce(&Self),
+        B: FnOnce(&Self) + Send,
     {
-        self.state.upgrade().map_or_else(
-            || {
-                panic_any(StopThread);
-            },
-            |state| {
-                let global_state = GLOBAL_STATE.lock().unwrap();
-                if global_state.paused {
-                    drop(global_state);
-                    thread::park();
-                } else {
-                    let delay = global_state.delay;
-                    drop(global_state);
-                    thread::sleep(std::time::Duration::from_micros(delay));
+        let results = thread::scope(|scope| {
+            let other = self.spawn(scope, b);
+            [self.run(a), other.join().unwrap()]
+        });
+        Self::settle(results);
+    }
+
+    /// Runs `f(k, interface)` for every `k` in `0..n`, each on a thread with a
+    /// cursor of its own, and returns once all of them have finished.
+    pub fn parallel<F>(&self, n: usize, f: F)
+    where
+        F: Fn(usize, &Self) + Sync,
+    {
+        let f = &f;
+        let results = thread::scope(|scope| {
+            let others = (1..n)
+                .map(|k| self.spawn(scope, move |int| f(k, int)))
+                .collect::<Vec<_>>();
+            let mut results = vec![self.run(|int| f(0, int))];
+            results.extend(others.into_iter().map(|other| other.join().unwrap()));
+            results
+        });
+        Self::settle(results);
+    }
+
+    /// Runs `f` on a new scoped thread with a cursor of its own.
+    fn spawn<'scope, F>(
+        &self,
+        scope: &'scope Scope<'scope, '_>,
+        f: F,
+    ) -> ScopedJoinHandle<'scope, Result<(), Unwound>>
+    where
+        F: FnOnce(&Self) + Send + 'scope,
+    {
+        let child = self.fork();
+        scope.spawn(move || {
+            SORT_THREAD.set(true);
+            let child = child.attach();
+            let result = child.run(f);
+            child.detach();
+            result
+        })
+    }
+
+    /// Runs `f` on this cursor, catching what it unwinds with. Anything but a
+    /// cancellation stops the other cursors of the run.
+    fn run<F: FnOnce(&Self)>(&self, f: F) -> Result<(), Unwound> {
+        catch_unwind(AssertUnwindSafe(|| f(self))).map_err(|payload| {
+            if !payload.is::<Cancelled>() {
+                self.cancel.store(true, atomic::Ordering::Relaxed);
+                let state = self.state.lock().unwrap();
+                state.unpark();
+            }
+            (payload, PANIC_LOCATION.take())
+        })
+    }
+
+    /// Resumes unwinding with the first failure among `results`, preferring
+    /// panics and aborts over the cancellations they caused.
+    fn settle(results: impl IntoIterator<Item = Result<(), Unwound>>) {
+        let mut cancelled = None;
+        for result in results {
+            match result {
+                Ok(()) => {}
+                Err((payload, _)) if payload.is::<Cancelled>() => cancelled = Some(payload),
+                Err((payload, location)) => {
+                    PANIC_LOCATION.set(location);
+                    resume_unwind(payload);
                 }
+            }
+        }
+        if let Some(payload) = cancelled {
+            resume_unwind(payload);
+        }
+    }
 
-                let mut state = state.lock().unwrap();
-                (f)(&mut state)
-            },
-        )
+    /// Performs an operation of the given `cost` on the state, after waiting
+    /// for the pause and speed settings.
+    #[track_caller]
+    fn modify_state<F, T>(&self, step: Option<Step>, cost: u64, f: F) -> T
+    where
+        F: FnOnce(&mut State) -> T,
+    {
+        if self.throttled {
+            self.throttle(cost);
+        }
+        self.checkpoint();
+        #[cfg(feature = "fallible")]
+        if let Some(injector) = &self.injector {
+            injector.operation();
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if self.budget.is_some_and(|budget| state.ops >= budget) {
+            drop(state);
+            resume_unwind(Box::new(Abort::Budget));
+        }
+        state.line = self.line.get();
+        state.ops += 1;
+        let compares = state.counters.compares;
+        if let (Some(step), Some(Some(cursor))) = (&step, state.cursors.get_mut(self.cursor)) {
+            cursor.step = Some(step.clone());
+        }
+        if let (Some(step), Some(cache)) = (&step, &mut state.cache) {
+            cache.step(step);
+        }
+        if let (Some(step), Some(disk)) = (&step, &mut state.disk) {
+            disk.step(step);
+        }
+        if let Some(step) = step.as_ref().filter(|_| !state.taps.is_empty()) {
+            let op = state.ops;
+            state.taps.retain(|tap| {
+                !matches!(
+                    tap.try_send((op, step.clone())),
+                    Err(TrySendError::Disconnected(_))
+                )
+            });
+        }
+        let result = (f)(&mut state);
+        if let Some(recorder) = &state.recorder {
+            recorder.lock().unwrap().step(state.ops, &state.data);
+        }
+        // Sent before unlocking, so that lines follow the order of operations.
+        if let (Some(tracer), Some(step)) = (&self.trace, &step) {
+            tracer.send(tracer.operation(step, self.cursor, &state), &self.cancel);
+        }
+        let hit =
+            self.throttled && step.is_some_and(|step| state.check_breakpoints(&step, compares));
+        drop(state);
+        if hit {
+            GLOBAL_STATE.lock().unwrap().paused = true;
+        }
+        result
     }
 
+    /// Waits according to the pause and speed settings, sleeping for `cost`
+    /// times the delay.
+    fn throttle(&self, cost: u64) {
+        let global_state = GLOBAL_STATE.lock().unwrap();
+        if global_state.paused {
+            drop(global_state);
+            // This thread may have attached after `Sorter::stop` woke the others.
+            self.check_cancelled();
+            thread::park();
+            return;
+        }
+        match global_state.speed {
+            Speed::Delay => {
+                let delay = global_state.delay.saturating_mul(cost);
+                drop(global_state);
+                self.sleep(Duration::from_micros(delay));
+            }
+            Speed::PerFrame => {
+                drop(global_state);
+                self.wait_for_frame();
+            }
+        }
+    }
+
+    /// Sleeps for `duration`, waking up early when the run is cancelled.
+    fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.cancel.load(atomic::Ordering::Relaxed) {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+
+    /// Blocks until the UI has handed out an operation for the current frame.
+    fn wait_for_frame(&self) {
+        loop {
+            self.check_cancelled();
+            let mut state = self.state.lock().unwrap();
+            if state.allowance >= 1.0 {
+                state.allowance -= 1.0;
+                return;
+            }
+            drop(state);
+            thread::park();
+        }
+    }
+
+    /// Lets work between operations, such as that of an interpreter, be
+    /// stopped: unwinds out of the method if the run has been cancelled or
+    /// has timed out.
+    pub fn checkpoint(&self) {
+        self.check_cancelled();
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            resume_unwind(Box::new(Abort::Timeout));
+        }
+    }
+
+    /// Unwinds out of the method if its run has been cancelled.
+    fn check_cancelled(&self) {
+        if self.cancel.load(atomic::Ordering::Relaxed) {
+            resume_unwind(Box::new(Cancelled));
+        }
+    }
+
+    #[track_caller]
     pub fn read(&self, i: usize) -> u32 {
-        self.modify_state(|state| {
-            state.step = Some(Step::Read(i));
-            state.data[i]
-        })
+        let (value, len) = self.modify_state(Some(Step::Read(i)), self.costs.read, |state| {
+            state.counters.reads += 1;
+            (state.get(i), state.extent())
+        });
+        self.load(value.unwrap_or_else(|| out_of_bounds(len, i)))
+    }
+
+    /// Passes an element taken from the array through the fault injector, so
+    /// that reads and comparisons alike count towards `Fault::WrongRead`.
+    #[cfg_attr(not(feature = "fallible"), allow(clippy::unused_self))]
+    fn load(&self, value: u32) -> u32 {
+        #[cfg(feature = "fallible")]
+        if let Some(injector) = &self.injector {
+            return injector.read(value);
+        }
+        value
+    }
+
+    /// Compares the elements at `i` and `j`.
+    #[track_caller]
+    pub fn compare(&self, i: usize, j: usize) -> Ordering {
+        let (values, len) =
+            self.modify_state(Some(Step::Compare(i, j)), self.costs.compare, |state| {
+                state.counters.compares += 1;
+                (state.get(i).zip(state.get(j)), state.extent())
+            });
+        let (a, b) = values.unwrap_or_else(|| out_of_bounds(len, i.max(j)));
+        self.judge.compare(self.load(a), self.load(b))
     }
 
+    /// Compares the element at `i` with `value`, e.g. a pivot read earlier.
+    #[track_caller]
+    pub fn compare_to(&self, i: usize, value: u32) -> Ordering {
+        let (a, len) = self.modify_state(Some(Step::Read(i)), self.costs.compare, |state| {
+            state.counters.compares += 1;
+            (state.get(i), state.extent())
+        });
+        let a = a.unwrap_or_else(|| out_of_bounds(len, i));
+        self.judge.compare(self.load(a), value)
+    }
+
+    #[track_caller]
     pub fn swap(&self, i: usize, j: usize) {
-        self.modify_state(|state| {
-            state.step = Some(Step::Swap(i, j));
-            state.data.swap(i, j);
+        #[cfg(feature = "fallible")]
+        let perform = self
+            .injector
+            .as_ref()
+            .is_none_or(|injector| injector.swap());
+        #[cfg(not(feature = "fallible"))]
+        let perform = true;
+        let len = self.modify_state(Some(Step::Swap(i, j)), self.costs.swap, |state| {
+            state.counters.swaps += 1;
+            if perform && i.max(j) < state.extent() {
+                state.swap(i, j);
+                state.record(Change::Swap {
+                    cursor: self.cursor,
+                    i,
+                    j,
+                });
+            }
+            state.extent()
         });
+        if i.max(j) >= len {
+            out_of_bounds(len, i.max(j));
+        }
     }
 
+    /// Overwrites the element at `i` with `value`, e.g. one kept aside by the
+    /// method. Unlike swaps, writes can lose or duplicate elements.
+    #[track_caller]
+    pub fn write(&self, i: usize, value: u32) {
+        let len = self.modify_state(Some(Step::Write(i)), self.costs.write, |state| {
+            state.counters.writes += 1;
+            if let Some(old) = state.get(i) {
+                state.set(i, value);
+                state.record(Change::Write {
+                    cursor: self.cursor,
+                    i,
+                    old,
+                    value,
+                });
+            }
+            state.extent()
+        });
+        if i >= len {
+            out_of_bounds(len, i);
+        }
+    }
+
+    /// The number of elements. Not an operation: it is neither counted nor
+    /// throttled.
     pub fn len(&self) -> usize {
-        self.modify_state(|state| state.data.len())
+        self.checkpoint();
+        self.state.lock().unwrap().data.len()
+    }
+
+    /// Labels the part of the method that is executing, e.g. the current gap of
+    /// shell sort. Entering a different phase triggers `Breakpoint::Phase`.
+    pub fn phase(&self, label: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.phase.as_deref() == Some(label) {
+            return;
+        }
+        state.phase = Some(label.to_owned());
+        let hit = self.throttled && state.breakpoints.contains(&Breakpoint::Phase);
+        if hit {
+            state.hit = Some(Breakpoint::Phase);
+        }
+        drop(state);
+        if hit {
+            GLOBAL_STATE.lock().unwrap().paused = true;
+        }
+    }
+
+    /// Marks the layer of a sorting network that is executing, for the network
+    /// view. It also becomes the phase.
+    pub fn layer(&self, layer: usize) {
+        self.state.lock().unwrap().layer = Some(layer);
+        self.phase(&format!("layer {}", layer + 1));
+    }
+
+    /// Moves the run onto the simulated disk, with `files` scratch files after
+    /// the array, and returns the memory it may use. Runs without a disk get
+    /// one with the default `external::Config`.
+    pub fn disk(&self, files: usize) -> external::Config {
+        let mut state = self.state.lock().unwrap();
+        let len = state.data.len();
+        let disk = state
+            .disk
+            .get_or_insert_with(|| Disk::new(external::Config::default(), len));
+        disk.open(files);
+        let config = disk.config;
+        drop(state);
+        config
+    }
+
+    /// Gives the page holding element `i` a frame of memory without reading it,
+    /// for a page the method is about to overwrite.
+    pub fn claim(&self, i: usize) {
+        if let Some(disk) = &mut self.state.lock().unwrap().disk {
+            disk.claim(i);
+        }
+    }
+
+    /// Writes the page holding element `i` back to disk, freeing its frame.
+    pub fn evict(&self, i: usize) {
+        if let Some(disk) = &mut self.state.lock().unwrap().disk {
+            disk.evict(i, true);
+        }
+    }
+
+    /// Frees the frame of the page holding element `i` without writing it back,
+    /// once the method no longer needs what it changed there.
+    pub fn discard(&self, i: usize) {
+        if let Some(disk) = &mut self.state.lock().unwrap().disk {
+            disk.evict(i, false);
+        }
+    }
+
+    /// Marks the 1-based `line` of the method's code as executing. It is shown
+    /// together with the next operation.
+    pub fn line(&self, line: usize) {
+        self.line.set(Some(line));
+    }
+}
+
+/// A barrier for the cursors of `Interface::parallel`, which unwinds out of
+/// the method like any operation if the run is stopped while they wait.
+pub struct Lockstep {
+    cursors: usize,
+    /// Barriers passed so far, and the threads waiting at the current one.
+    waiting: Mutex<(usize, Vec<Thread>)>,
+}
+
+impl Lockstep {
+    pub const fn new(cursors: usize) -> Self {
+        Self {
+            cursors,
+            waiting: Mutex::new((0, Vec::new())),
+        }
+    }
+
+    /// Blocks until all the cursors have called `wait`.
+    pub fn wait(&self, int: &Interface) {
+        let mut waiting = self.waiting.lock().unwrap();
+        let passed = waiting.0;
+        if waiting.1.len() + 1 >= self.cursors {
+            waiting.0 += 1;
+            waiting.1.drain(..).for_each(|thread| thread.unpark());
+            return;
+        }
+        waiting.1.push(thread::current());
+        drop(waiting);
+        // `Sorter::stop` unparks every cursor after cancelling the run.
+        while self.waiting.lock().unwrap().0 == passed {
+            int.check_cancelled();
+            thread::park();
+        }
     }
 }
 
 pub struct Method {
     pub name: &'static str,
     pub func: fn(Interface),
+    /// Pseudocode shown in the code panel, one entry per line.
+    pub code: &'static [&'static str],
+    pub info: Info,
+}
+
+/// Descriptive metadata about a `Method`.
+#[allow(clippy::struct_excessive_bools)]
+pub struct Info {
+    /// Best, average and worst case time complexity.
+    pub best: &'static str,
+    pub average: &'static str,
+    pub worst: &'static str,
+    /// Whether the worst case terminates in a time bounded by the size of the
+    /// array; random methods such as bogo sort may run forever.
+    pub bounded: bool,
+    /// Auxiliary memory used on top of the array.
+    pub memory: &'static str,
+    /// Whether equal elements keep their relative order.
+    pub stable: bool,
+    /// Whether the array is rearranged without a second copy of it.
+    pub in_place: bool,
+    /// Whether presorted input is handled faster.
+    pub adaptive: bool,
+    pub description: &'static str,
+    pub references: &'static [&'static str],
 }
 
 #[derive(Clone)]
 pub struct State {
     pub sorting: bool,
     pub data: Vec<u32>,
-    pub step: Option<Step>,
+    /// The threads of the current run, indexed by `Interface::cursor`. Slots of
+    /// finished threads are `None` until reused.
+    pub cursors: Vec<Option<Cursor>>,
+    /// Line of the method's code that performed `step`.
+    pub line: Option<usize>,
+    /// Operations the sort thread may still perform in `Speed::PerFrame` mode.
+    pub allowance: f64,
+    /// Operations performed in the current run.
+    pub ops: u64,
+    pub counters: Counters,
+    /// Cost model the virtual time of the run is measured in.
+    pub costs: Costs,
+    /// Label set by the method through `Interface::phase`.
+    pub phase: Option<String>,
+    /// Layer of a sorting network set through `Interface::layer`.
+    pub layer: Option<usize>,
+    pub breakpoints: Vec<Breakpoint>,
+    /// The breakpoint that paused the run.
+    pub hit: Option<Breakpoint>,
+    /// Recent changes to `data`, for stepping back.
+    pub journal: VecDeque<Change>,
+    /// Changes undone by stepping back, most recent last.
+    pub redo: Vec<Change>,
+    /// Simulated cache fed by the operations of the run, if enabled.
+    pub cache: Option<Cache>,
+    /// Simulated disk holding the array and scratch files, if enabled.
+    pub disk: Option<Disk>,
+    /// Set when the last run was aborted by its `Limits`.
+    pub aborted: Option<Abort>,
+    /// Set when the method of the last run panicked.
+    pub failure: Option<Failure>,
+    /// Fingerprint of the elements of the array when the run started.
+    pub input: u64,
+    /// Receivers of every step and the operation it was, such as remote
+    /// clients. Steps a full one has no room for are dropped.
+    pub taps: Vec<SyncSender<(u64, Step)>>,
+    /// Where the array is written during the run, if anywhere.
+    pub recorder: Option<Arc<Mutex<Recorder>>>,
     pub start_time: Option<Instant>,
     pub stop_time: Option<Instant>,
 }
 
+/// A panic raised by a method, captured on its sort thread.
+#[derive(Clone)]
+pub struct Failure {
+    pub message: String,
+    /// `file:line` of the panic, when known.
+    pub location: Option<String>,
+    /// Number of operations performed before the panic.
+    pub operation: u64,
+    /// The array at the time of the panic.
+    pub data: Vec<u32>,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "Panic at {location}: {}", self.message),
+            None => write!(f, "Panic: {}", self.message),
+        }
+    }
+}
+
+/// Stack of the sort thread, enough for scripts recursing as deep as they may.
+const STACK_SIZE: usize = 256 << 20;
+
+thread_local! {
+    /// Whether the current thread runs a method.
+    static SORT_THREAD: Cell<bool> = const { Cell::new(false) };
+    /// Location of the last panic on this thread, recorded by the panic hook.
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Wraps the process-wide panic hook so that panics on sort threads are recorded
+/// for their `Failure` instead of being printed. Other threads are unaffected.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = take_hook();
+        set_hook(Box::new(move |panic_info| {
+            if SORT_THREAD.get() {
+                let location = panic_info
+                    .location()
+                    .map(|l| format!("{}:{}", l.file(), l.line()));
+                PANIC_LOCATION.set(location);
+            } else {
+                previous(panic_info);
+            }
+        }));
+    });
+}
+
+/// A hash of the multiset of elements of `data`, independent of their order.
+fn fingerprint(data: &[u32]) -> u64 {
+    data.iter().fold(0, |hash, &v| {
+        // SplitMix64's finalizer, so that sums of elements don't collide.
+        let mut z = u64::from(v).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash.wrapping_add(z ^ (z >> 31))
+    })
+}
+
+/// Panics outside of `modify_state`, so that the state mutex isn't poisoned.
+#[track_caller]
+fn out_of_bounds(len: usize, i: usize) -> ! {
+    panic!("index out of bounds: the len is {len} but the index is {i}");
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map_or_else(
+        || {
+            payload
+                .downcast_ref::<String>()
+                .map_or_else(|| format!("{payload:?}"), std::clone::Clone::clone)
+        },
+        |s| String::from(*s),
+    )
+}
+
+impl State {
+    /// Whether the array holds the same elements as at the start of the run,
+    /// as far as `fingerprint` can tell.
+    pub fn is_permutation_of_input(&self) -> bool {
+        fingerprint(&self.data) == self.input
+    }
+
+    /// Virtual time the run has taken so far under its cost model.
+    pub fn virtual_time(&self) -> u64 {
+        self.costs
+            .time(self.counters, self.disk.as_ref().map(|disk| disk.stats))
+    }
+
+    /// Wakes up every thread of the run.
+    fn unpark(&self) {
+        for cursor in self.cursors.iter().flatten() {
+            cursor.thread.unpark();
+        }
+    }
+
+    /// The element at address `i`: an index into the array or, past its end,
+    /// into the scratch files of the disk.
+    pub fn get(&self, i: usize) -> Option<u32> {
+        let len = self.data.len();
+        match &self.disk {
+            Some(disk) if i >= len && len > 0 => {
+                disk.files.get(i / len - 1).map(|file| file[i % len])
+            }
+            _ => self.data.get(i).copied(),
+        }
+    }
+
+    fn slot(&mut self, i: usize) -> &mut u32 {
+        let len = self.data.len();
+        match &mut self.disk {
+            Some(disk) if i >= len && len > 0 => &mut disk.files[i / len - 1][i % len],
+            _ => &mut self.data[i],
+        }
+    }
+
+    /// Swaps the elements at addresses `i` and `j`, which must be in bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        let (a, b) = (*self.slot(i), *self.slot(j));
+        *self.slot(i) = b;
+        *self.slot(j) = a;
+    }
+
+    /// Sets the element at address `i`, which must be in bounds.
+    pub fn set(&mut self, i: usize, value: u32) {
+        *self.slot(i) = value;
+    }
+
+    /// Number of addresses, scratch files included.
+    pub fn extent(&self) -> usize {
+        self.disk.as_ref().map_or(self.data.len(), Disk::extent)
+    }
+
+    fn new(data: Vec<u32>) -> Self {
+        Self {
+            sorting: false,
+            data,
+            cursors: Vec::new(),
+            line: None,
+            allowance: 0.0,
+            ops: 0,
+            counters: Counters::default(),
+            costs: Costs::default(),
+            phase: None,
+            layer: None,
+            breakpoints: Vec::new(),
+            hit: None,
+            journal: VecDeque::new(),
+            redo: Vec::new(),
+            cache: None,
+            disk: None,
+            aborted: None,
+            failure: None,
+            input: fingerprint(&[]),
+            taps: Vec::new(),
+            recorder: None,
+            start_time: None,
+            stop_time: None,
+        }
+    }
+}
+
+/// Runs `method` over `data` on the current thread, without any throttling.
+pub fn apply(method: &Method, data: Vec<u32>) -> Vec<u32> {
+    let state = Arc::new(Mutex::new(State::new(data)));
+    (method.func)(Interface::unthrottled(
+        state.clone(),
+        Arc::new(AtomicBool::new(false)),
+    ));
+    let state = Arc::into_inner(state).unwrap();
+    state.into_inner().unwrap().data
+}
+
 pub struct Sorter {
     pub state: Arc<Mutex<State>>,
-    pub method: Option<fn(Interface)>,
+    pub method: Option<&'static Method>,
+    /// Whether runs follow the pause and speed settings; headless runs don't.
+    pub throttled: bool,
+    pub comparator: Comparator,
+    /// Truthful answers before `Comparator::LiesAfter` starts lying.
+    pub lie_after: u64,
+    pub limits: Limits,
+    /// Cache hierarchy to simulate during runs, if any.
+    pub cache: Option<cache::Config>,
+    /// Memory and page size of the disk to simulate during runs, if any.
+    pub external: Option<external::Config>,
+    pub costs: Costs,
+    /// Where tracked runs write their operations, if anywhere.
+    pub trace: Option<Arc<Tracer>>,
+    /// Where tracked runs write the array every so many operations, if
+    /// anywhere.
+    pub recorder: Option<Arc<Mutex<Recorder>>>,
+    /// Cancellation token of the current run.
+    cancel: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>,
 }
 
 impl Sorter {
     pub fn new(data: Vec<u32>) -> Self {
-        let state = Arc::new(Mutex::new(State {
-            sorting: false,
-            data,
-            step: None,
-            start_time: None,
-            stop_time: None,
-        }));
+        let state = Arc::new(Mutex::new(State::new(data)));
 
         Self {
             state,
             method: None,
+            throttled: true,
+            comparator: Comparator::Consistent,
+            lie_after: 0,
+            limits: Limits::default(),
+            cache: None,
+            external: None,
+            costs: Costs::default(),
+            trace: None,
+            recorder: None,
+            cancel: Arc::new(AtomicBool::new(false)),
             handle: None,
         }
     }
@@ -108,88 +929,270 @@ impl Sorter {
         state.sorting
     }
 
+    #[allow(clippy::too_many_lines)]
     pub fn start(&mut self, track: bool) {
-        let state = self.state.clone();
         {
-            let mut state1 = state.lock().unwrap();
+            let mut state = self.state.lock().unwrap();
 
-            if state1.sorting {
+            if state.sorting {
                 return;
             }
-            state1.sorting = true;
+            state.sorting = true;
+            state.ops = 0;
+            state.counters = Counters::default();
+            state.costs = self.costs;
+            state.input = fingerprint(&state.data);
+            state.aborted = None;
+            state.failure = None;
+            state.phase = None;
+            state.layer = None;
+            state.hit = None;
+            state.journal.clear();
+            state.redo.clear();
+            state.cache = self.cache.clone().map(Cache::new);
+            let len = state.data.len();
+            state.disk = self.external.map(|config| Disk::new(config, len));
+            state.recorder = self.recorder.clone().filter(|_| track);
+            if let Some(recorder) = &state.recorder {
+                recorder.lock().unwrap().begin(&state.data);
+            }
             if track {
-                state1.start_time = Some(Instant::now());
+                state.start_time = Some(Instant::now());
             }
         }
 
-        if let Some(method) = self.method {
-            self.handle = Some(spawn(move || {
-                set_hook(Box::new(move |panic_info| {
-                    let payload = panic_info.payload();
-
-                    if !payload.is::<StopThread>() {
-                        let mut global = GLOBAL_STATE.lock().unwrap();
-                        let payload_str = payload.downcast_ref::<&str>().map_or_else(
-                            || {
-                                payload.downcast_ref::<String>().map_or_else(
-                                    || format!("{payload:?}"),
-                                    std::clone::Clone::clone,
-                                )
-                            },
-                            |s| String::from(*s),
-                        );
-
-                        global.panic = panic_info
-                            .location()
-                            .map_or_else(
-                                || format!("Panic: {payload_str}"),
-                                |location| {
-                                    format!(
-                                        "Panic at {}:{}: {}",
-                                        location.file(),
-                                        location.line(),
-                                        payload_str
-                                    )
-                                },
-                            )
-                            .into();
-                    }
-                }));
+        // The previous run has finished on its own; reap its thread.
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
 
-                let state1 = Arc::downgrade(&state);
-                drop(state);
+        let Some(method) = self.method else {
+            panic!("No method");
+        };
+        let state = self.state.clone();
+        self.cancel = Arc::new(AtomicBool::new(false));
+        let cancel = self.cancel.clone();
+        #[cfg(feature = "fallible")]
+        let fault = if track {
+            let global = GLOBAL_STATE.lock().unwrap();
+            (global.fault, global.fault_at)
+        } else {
+            (Fault::None, 0)
+        };
+        let (throttled, comparator, lie_after) = (self.throttled, self.comparator, self.lie_after);
+        let costs = self.costs;
+        let tracer = self.trace.clone().filter(|_| track);
+        let limits = if track {
+            self.limits
+        } else {
+            Limits::default()
+        };
+        install_panic_hook();
+        let thread = thread::Builder::new().stack_size(STACK_SIZE);
+        self.handle = Some(
+            thread
+                .spawn(move || {
+                    SORT_THREAD.set(true);
+                    if let Some(tracer) = &tracer {
+                        let line = tracer.begin(method.name, &state.lock().unwrap().data);
+                        tracer.send(line, &cancel);
+                    }
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        let interface = if throttled {
+                            Interface::new(state.clone(), cancel.clone())
+                        } else {
+                            Interface::unthrottled(state.clone(), cancel.clone())
+                        };
+                        let interface = interface
+                            .with_comparator(comparator, lie_after)
+                            .with_limits(limits)
+                            .with_costs(costs);
+                        let interface = match tracer.clone() {
+                            Some(tracer) => interface.with_trace(tracer),
+                            None => interface,
+                        };
+                        #[cfg(feature = "fallible")]
+                        let interface = match fault {
+                            (Fault::None, _) => interface,
+                            (fault, n) => interface.with_injector(Injector::new(fault, n)),
+                        };
+                        (method.func)(interface);
+                    }));
 
-                method(Interface::new(state1.clone()));
+                    // A cancelled run is reset by `stop`.
+                    if result
+                        .as_ref()
+                        .is_err_and(|payload| payload.is::<Cancelled>())
+                    {
+                        {
+                            let mut state = state.lock().unwrap();
+                            if let Some(recorder) = state.recorder.take() {
+                                recorder.lock().unwrap().finish(state.ops, &state.data);
+                            }
+                        }
+                        if let Some(tracer) = &tracer {
+                            let line = tracer.end("stopped", &state.lock().unwrap());
+                            tracer.send(line, &cancel);
+                        }
+                        return;
+                    }
+                    let mut state = state.lock().unwrap();
+                    let outcome = match result {
+                        Ok(()) => String::from("done"),
+                        Err(payload) => {
+                            if let Some(&abort) = payload.downcast_ref::<Abort>() {
+                                state.aborted = Some(abort);
+                                abort.to_string()
+                            } else {
+                                let failure = Failure {
+                                    message: panic_message(payload.as_ref()),
+                                    location: PANIC_LOCATION.take(),
+                                    operation: state.ops,
+                                    data: state.data.clone(),
+                                };
+                                let outcome = failure.to_string();
+                                state.failure = Some(failure);
+                                outcome
+                            }
+                        }
+                    };
+                    if let Some(disk) = &mut state.disk {
+                        disk.flush();
+                    }
+                    if let Some(recorder) = state.recorder.take() {
+                        recorder.lock().unwrap().finish(state.ops, &state.data);
+                    }
+                    state.sorting = false;
+                    state.cursors.clear();
+                    state.line = None;
+                    if track {
+                        state.stop_time = Some(Instant::now());
+                    }
+                    if let Some(tracer) = &tracer {
+                        let line = tracer.end(&outcome, &state);
+                        drop(state);
+                        tracer.send(line, &cancel);
+                    }
+                })
+                .unwrap(),
+        );
+    }
 
-                let state1 = state1.upgrade().unwrap();
-                let mut state1 = state1.lock().unwrap();
-                state1.sorting = false;
-                state1.step = None;
-                if track {
-                    state1.stop_time = Some(Instant::now());
-                }
-            }));
-        } else {
-            panic!("No method");
+    /// Blocks until the current run has finished on its own.
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
         }
     }
 
+    /// Redoes any changes undone by stepping back and lets the sort thread continue.
     pub fn resume(&self) {
-        if let Some(handle) = &self.handle {
-            handle.thread().unpark();
+        let mut state = self.state.lock().unwrap();
+        while state.step_forward() {}
+        state.unpark();
+    }
+
+    /// Redoes one change undone by stepping back, or else lets every thread of
+    /// the run perform one more operation, even when throttled per frame.
+    pub fn step(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.step_forward() {
+            return;
         }
+        state.allowance += 1.0;
+        drop(state);
+        self.resume();
     }
 
-    pub fn stop(&mut self) {
-        let state_clone = self.state.clone();
-        let state = state_clone;
-        let mut state = state.lock().unwrap();
-        state.sorting = false;
-        state.step = None;
-        self.state = Arc::new(Mutex::new(state.clone()));
+    /// Undoes the most recent change to the array while paused.
+    pub fn step_back(&self) {
+        self.state.lock().unwrap().step_back();
+    }
+
+    /// Hands out `ops` operations for the next frame, keeping fractional leftovers
+    /// so that rates below one operation per frame still make progress.
+    pub fn advance_frame(&self, ops: f64) {
+        let mut state = self.state.lock().unwrap();
+        if !state.sorting {
+            return;
+        }
+        state.allowance = state.allowance.fract() + ops;
         drop(state);
+        self.resume();
+    }
+
+    /// Cancels the current run and waits for its thread to exit.
+    ///
+    /// Must not be called while holding `GLOBAL_STATE` or `state`, which the sort
+    /// thread may be waiting on.
+    pub fn stop(&mut self) {
+        self.cancel.store(true, atomic::Ordering::Relaxed);
+        self.state.lock().unwrap().unpark();
         if let Some(handle) = self.handle.take() {
             handle.thread().unpark();
+            handle.join().unwrap();
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.sorting = false;
+        state.cursors.clear();
+        state.line = None;
+        state.allowance = 0.0;
+        state.redo.clear();
+    }
+}
+
+impl Drop for Sorter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::METHODS;
+
+    /// Threads of this process.
+    fn threads() -> usize {
+        std::fs::read_dir("/proc/self/task").unwrap().count()
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn stopping_runs_leaves_no_threads() {
+        let bubble = METHODS.iter().find(|m| m.name == "bubble").unwrap();
+        let mut sorter = Sorter::new((1..=50).rev().collect());
+        sorter.method = Some(bubble);
+        let baseline = threads();
+        for _ in 0..1000 {
+            sorter.start(true);
+            sorter.stop();
+            sorter.state.lock().unwrap().data = (1..=50).rev().collect();
+        }
+        // Other tests may be running sorts of their own meanwhile.
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while threads() > baseline && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(threads() <= baseline);
+    }
+
+    #[test]
+    fn parallel_methods_sort_and_stop() {
+        for name in ["odd-even", "bitonic", "odd-even-merge", "pairwise"] {
+            let method = METHODS.iter().find(|m| m.name == name).unwrap();
+            for n in [0, 1, 2, 3, 100] {
+                let sorted = apply(method, (1..=n).rev().collect());
+                assert_eq!(sorted, (1..=n).collect::<Vec<_>>(), "{name}");
+            }
+            // Stopping must wake cursors waiting for one another.
+            let mut sorter = Sorter::new((1..=100).rev().collect());
+            sorter.method = Some(method);
+            for _ in 0..20 {
+                sorter.start(true);
+                sorter.stop();
+            }
         }
     }
 }
@@ -0,0 +1,125 @@
+use crate::comparator::Comparator;
+use crate::methods::{rng, METHODS};
+use crate::sorter::{Method, Sorter};
+use rand::seq::SliceRandom;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Comparators every method is run against.
+const COMPARATORS: [Comparator; 3] = [
+    Comparator::Random,
+    Comparator::NonTransitive,
+    Comparator::LiesAfter,
+];
+
+/// How a method coped with an inconsistent comparator.
+enum Verdict {
+    /// Finished with the array still a permutation of the input.
+    Ok,
+    /// Finished, but elements were lost or duplicated.
+    Corrupted,
+    Panicked(String),
+    /// Still running when the watchdog fired.
+    TimedOut,
+}
+
+impl Verdict {
+    const fn label(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Corrupted => "corrupted",
+            Self::Panicked(_) => "panicked",
+            Self::TimedOut => "timed out",
+        }
+    }
+}
+
+/// Runs every method in `METHODS` on `size` shuffled elements against each
+/// inconsistent comparator, stopping runs that exceed `timeout`.
+///
+/// Prints a report and returns whether every run came out `Verdict::Ok`. Methods
+/// that are not `Info::bounded`, such as bogo sort, cannot be expected to
+/// finish, so their timeouts are reported as `unbounded` and do not count.
+pub fn run(size: u32, timeout: Duration) -> bool {
+    let mut input = (1..=size).collect::<Vec<u32>>();
+    input.shuffle(&mut rng());
+
+    print!("{:<14}", "method");
+    for comparator in COMPARATORS {
+        print!(" {:<15}", comparator.name());
+    }
+    println!();
+
+    let mut details = Vec::new();
+    for method in METHODS {
+        print!("{:<14}", method.name);
+        for comparator in COMPARATORS {
+            let verdict = check(method, comparator, &input, u64::from(size), timeout);
+            let unbounded = !method.info.bounded;
+            match verdict {
+                Verdict::TimedOut if unbounded => print!(" {:<15}", "unbounded"),
+                _ => print!(" {:<15}", verdict.label()),
+            }
+            match verdict {
+                Verdict::Ok => {}
+                Verdict::TimedOut if unbounded => {}
+                Verdict::Panicked(message) => {
+                    details.push(format!(
+                        "{} / {}: {message}",
+                        method.name,
+                        comparator.name()
+                    ));
+                }
+                _ => details.push(format!("{} / {}", method.name, comparator.name())),
+            }
+        }
+        println!();
+    }
+
+    if !details.is_empty() {
+        println!("\nfailures:");
+        for detail in &details {
+            println!("  {detail}");
+        }
+    }
+    details.is_empty()
+}
+
+fn check(
+    method: &'static Method,
+    comparator: Comparator,
+    input: &[u32],
+    lie_after: u64,
+    timeout: Duration,
+) -> Verdict {
+    let mut sorter = Sorter::new(input.to_vec());
+    sorter.method = Some(method);
+    sorter.throttled = false;
+    sorter.comparator = comparator;
+    sorter.lie_after = lie_after;
+    sorter.start(false);
+
+    let deadline = Instant::now() + timeout;
+    while sorter.is_sorting() {
+        if Instant::now() >= deadline {
+            sorter.stop();
+            return Verdict::TimedOut;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let mut state = sorter.state.lock().unwrap();
+    if let Some(failure) = state.failure.take() {
+        return Verdict::Panicked(failure.to_string());
+    }
+    let mut output = state.data.clone();
+    drop(state);
+    output.sort_unstable();
+    let mut expected = input.to_vec();
+    expected.sort_unstable();
+    if output == expected {
+        Verdict::Ok
+    } else {
+        Verdict::Corrupted
+    }
+}
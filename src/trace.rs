@@ -0,0 +1,162 @@
+use crate::json::quote;
+use crate::sorter::{State, Step};
+use std::fmt::Write as _;
+use std::io::{self, BufWriter, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{self, AtomicBool, AtomicU64};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Lines queued for the writer; once full, the sort thread waits for room.
+const BUFFER: usize = 1024;
+/// How often a sort thread waiting for room checks whether it was cancelled.
+const POLL: Duration = Duration::from_millis(1);
+
+/// Where a trace is written.
+pub enum Sink {
+    Stdout,
+    /// A Unix socket another program is listening on.
+    Socket(String),
+}
+
+impl Sink {
+    /// `-` for stdout, anything else being the path of a Unix socket.
+    pub fn parse(spec: &str) -> Self {
+        match spec {
+            "-" => Self::Stdout,
+            path => Self::Socket(path.to_owned()),
+        }
+    }
+}
+
+/// Streams the operations of tracked runs as newline-delimited JSON.
+///
+/// Each run starts with a `start` line holding the method and the array, is
+/// followed by one line per operation and ends with an `end` line. Lines are
+/// written by a thread of their own; when the reader falls behind, the sort
+/// threads wait for it instead of dropping operations.
+pub struct Tracer {
+    lines: SyncSender<String>,
+    /// Sequence number of the next operation of the run.
+    seq: AtomicU64,
+    /// When the current run started.
+    start: Mutex<Instant>,
+}
+
+impl Tracer {
+    /// Connects to `sink` and starts the thread writing to it.
+    pub fn open(sink: &Sink) -> io::Result<Self> {
+        let out: Box<dyn Write + Send> = match sink {
+            Sink::Stdout => Box::new(io::stdout()),
+            #[cfg(unix)]
+            Sink::Socket(path) => Box::new(UnixStream::connect(path)?),
+            #[cfg(not(unix))]
+            Sink::Socket(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix sockets are not supported on this platform",
+                ))
+            }
+        };
+        let (lines, receiver) = mpsc::sync_channel::<String>(BUFFER);
+        thread::spawn(move || {
+            let mut out = BufWriter::new(out);
+            let mut write = || -> io::Result<()> {
+                while let Ok(line) = receiver.recv() {
+                    writeln!(out, "{line}")?;
+                    for line in receiver.try_iter() {
+                        writeln!(out, "{line}")?;
+                    }
+                    out.flush()?;
+                }
+                Ok(())
+            };
+            // Dropping the receiver lets the sort threads carry on untraced.
+            if let Err(err) = write() {
+                eprintln!("trace stopped: {err}");
+            }
+        });
+        Ok(Self {
+            lines,
+            seq: AtomicU64::new(0),
+            start: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Queues `line`, waiting while the writer is behind unless `cancel` is set.
+    /// Lines are dropped once the writer has given up.
+    pub fn send(&self, mut line: String, cancel: &AtomicBool) {
+        loop {
+            match self.lines.try_send(line) {
+                Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                Err(TrySendError::Full(rejected)) => {
+                    if cancel.load(atomic::Ordering::Relaxed) {
+                        return;
+                    }
+                    line = rejected;
+                    thread::park_timeout(POLL);
+                }
+            }
+        }
+    }
+
+    /// The line opening a run of `method` over `data`; restarts the sequence
+    /// numbers and the clock.
+    pub fn begin(&self, method: &str, data: &[u32]) -> String {
+        self.seq.store(0, atomic::Ordering::Relaxed);
+        *self.start.lock().unwrap() = Instant::now();
+        let values = data.iter().map(u32::to_string).collect::<Vec<_>>();
+        format!(
+            "{{\"event\": \"start\", \"method\": {}, \"data\": [{}]}}",
+            quote(method),
+            values.join(", ")
+        )
+    }
+
+    /// The line for `step`, performed by `cursor`, with the values at its
+    /// indices once it is done. Must be called, and the line sent, with
+    /// `state` locked, so that sequence numbers and lines follow the order of
+    /// operations.
+    pub fn operation(&self, step: &Step, cursor: usize, state: &State) -> String {
+        let seq = self.seq.fetch_add(1, atomic::Ordering::Relaxed);
+        let time = self.start.lock().unwrap().elapsed().as_micros();
+        let (kind, indices) = match *step {
+            Step::Read(i) => ("read", vec![i]),
+            Step::Compare(i, j) => ("compare", vec![i, j]),
+            Step::Swap(i, j) => ("swap", vec![i, j]),
+            Step::Write(i) => ("write", vec![i]),
+        };
+        let mut line = format!(
+            "{{\"event\": \"op\", \"seq\": {seq}, \"time_us\": {time}, \"cursor\": {cursor}, \
+             \"kind\": \"{kind}\""
+        );
+        for (name, i) in ["i", "j"].into_iter().zip(&indices) {
+            write!(line, ", \"{name}\": {i}").unwrap();
+        }
+        let values = indices
+            .iter()
+            .map(|&i| {
+                state
+                    .get(i)
+                    .map_or_else(|| String::from("null"), |v| v.to_string())
+            })
+            .collect::<Vec<_>>();
+        write!(line, ", \"values\": [{}]}}", values.join(", ")).unwrap();
+        line
+    }
+
+    /// The line closing a run, saying how it went.
+    pub fn end(&self, outcome: &str, state: &State) -> String {
+        let time = self.start.lock().unwrap().elapsed().as_micros();
+        format!(
+            "{{\"event\": \"end\", \"time_us\": {time}, \"outcome\": {}, \"ops\": {}, \
+             \"sorted\": {}}}",
+            quote(outcome),
+            self.seq.load(atomic::Ordering::Relaxed),
+            state.data.is_sorted()
+        )
+    }
+}
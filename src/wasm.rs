@@ -0,0 +1,216 @@
+use crate::sorter::{Info, Interface, Method};
+use std::panic::resume_unwind;
+use std::sync::{Arc, Mutex};
+use wasmi::{
+    Caller, Config, Engine, ExternType, Linker, Module, Store, TrapCode, TypedResumableCall,
+    ValType,
+};
+
+/// Fuel a run may burn unless `--fuel` says otherwise, about one unit per
+/// instruction executed.
+pub const DEFAULT_FUEL: u64 = 1_000_000_000;
+
+/// Fuel handed out between checks for a stopped run or a passed deadline.
+const FUEL_SLICE: u64 = 1_000_000;
+
+/// Host functions a module may import from `env`, with their parameters and
+/// results. Elements and indices are passed as `i32`.
+const IMPORTS: [(&str, &[ValType], &[ValType]); 5] = [
+    ("len", &[], &[ValType::I32]),
+    ("read", &[ValType::I32], &[ValType::I32]),
+    ("swap", &[ValType::I32, ValType::I32], &[]),
+    ("compare", &[ValType::I32, ValType::I32], &[ValType::I32]),
+    ("write", &[ValType::I32, ValType::I32], &[]),
+];
+
+/// Runs the `sort` export of the module loaded with `load`.
+pub static METHOD: Method = Method {
+    name: "wasm",
+    func: |int| {
+        let module = MODULE.lock().unwrap().clone();
+        if let Some(module) = module {
+            run(int, &module);
+        }
+    },
+    code: &[],
+    info: Info {
+        best: "-",
+        average: "-",
+        worst: "-",
+        bounded: true,
+        memory: "-",
+        stable: false,
+        in_place: false,
+        adaptive: false,
+        description: "A method loaded from a WebAssembly module. It exports sort() and may \
+                      import len, read, swap, compare and write from env.",
+        references: &["https://webassembly.org/"],
+    },
+};
+
+/// A compiled module and the fuel each run of it gets.
+struct Loaded {
+    name: String,
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+}
+
+/// The module `METHOD` runs.
+static MODULE: Mutex<Option<Arc<Loaded>>> = Mutex::new(None);
+
+/// Compiles the module `name` from `bytes`, in the binary or text format, into
+/// the one run by `METHOD`.
+pub fn load(name: &str, bytes: &[u8], fuel: u64) -> Result<(), String> {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, bytes).map_err(|err| err.to_string())?;
+
+    match module.get_export("sort") {
+        Some(ExternType::Func(ty)) if ty.params().is_empty() && ty.results().is_empty() => {}
+        _ => return Err(String::from("the module does not export a function sort()")),
+    }
+    for import in module.imports() {
+        let known = IMPORTS.iter().find(|(name, _, _)| *name == import.name());
+        match (import.module(), known, import.ty().func()) {
+            ("env", Some((_, params, results)), Some(ty))
+                if ty.params() == *params && ty.results() == *results => {}
+            ("env", Some(_), _) => {
+                return Err(format!("env.{} has the wrong type", import.name()));
+            }
+            (module, _, _) => return Err(format!("unknown import {module}.{}", import.name())),
+        }
+    }
+
+    *MODULE.lock().unwrap() = Some(Arc::new(Loaded {
+        name: name.to_owned(),
+        engine,
+        module,
+        fuel,
+    }));
+    Ok(())
+}
+
+/// Imports and exports of the loaded module, for the code panel.
+pub fn lines() -> Vec<String> {
+    let module = MODULE.lock().unwrap().clone();
+    let Some(module) = module else {
+        return Vec::new();
+    };
+    let mut lines = vec![format!("module {}", module.name)];
+    for import in module.module.imports() {
+        lines.push(format!("import {}.{}", import.module(), import.name()));
+    }
+    for export in module.module.exports() {
+        lines.push(format!("export {}", export.name()));
+    }
+    lines.push(format!("fuel {}", module.fuel));
+    lines
+}
+
+/// What the host functions of a run reach.
+struct Host {
+    int: Interface,
+    /// Length of the array, read once at the start.
+    len: usize,
+}
+
+impl Host {
+    /// `i` as an index into the array.
+    fn index(&self, i: i32) -> Result<usize, wasmi::Error> {
+        usize::try_from(i)
+            .ok()
+            .filter(|&i| i < self.len)
+            .ok_or_else(|| {
+                wasmi::Error::new(format!("index {i} out of bounds for length {}", self.len))
+            })
+    }
+}
+
+fn linker(engine: &Engine) -> Linker<Host> {
+    let mut linker = Linker::new(engine);
+    linker
+        .func_wrap("env", "len", |caller: Caller<'_, Host>| {
+            i32::try_from(caller.data().int.len()).unwrap_or(i32::MAX)
+        })
+        .unwrap()
+        .func_wrap("env", "read", |caller: Caller<'_, Host>, i: i32| {
+            let host = caller.data();
+            Ok::<_, wasmi::Error>(host.int.read(host.index(i)?).cast_signed())
+        })
+        .unwrap()
+        .func_wrap("env", "swap", |caller: Caller<'_, Host>, i: i32, j: i32| {
+            let host = caller.data();
+            host.int.swap(host.index(i)?, host.index(j)?);
+            Ok::<_, wasmi::Error>(())
+        })
+        .unwrap()
+        .func_wrap(
+            "env",
+            "compare",
+            |caller: Caller<'_, Host>, i: i32, j: i32| {
+                let host = caller.data();
+                let ordering = host.int.compare(host.index(i)?, host.index(j)?);
+                Ok::<_, wasmi::Error>(i32::from(ordering as i8))
+            },
+        )
+        .unwrap()
+        .func_wrap(
+            "env",
+            "write",
+            |caller: Caller<'_, Host>, i: i32, value: i32| {
+                let host = caller.data();
+                host.int.write(host.index(i)?, value.cast_unsigned());
+                Ok::<_, wasmi::Error>(())
+            },
+        )
+        .unwrap();
+    linker
+}
+
+/// Instantiates `module` and calls its `sort` export, handing out the fuel in
+/// slices so that the run can be stopped between them. A trap ends the run as
+/// a failure, raised without the panic hook so that it carries no Rust
+/// location.
+fn run(int: Interface, module: &Loaded) {
+    let len = int.len();
+    let mut store = Store::new(&module.engine, Host { int, len });
+    store.set_fuel(module.fuel).unwrap();
+    if let Err(err) = call(&mut store, module) {
+        let message = if err.as_trap_code() == Some(TrapCode::OutOfFuel) {
+            format!("{}: out of fuel after {} units", module.name, module.fuel)
+        } else {
+            format!("{}: {err}", module.name)
+        };
+        resume_unwind(Box::new(message));
+    }
+}
+
+fn call(store: &mut Store<Host>, module: &Loaded) -> Result<(), wasmi::Error> {
+    let instance = linker(&module.engine).instantiate_and_start(&mut *store, &module.module)?;
+    let sort = instance.get_typed_func::<(), ()>(&*store, "sort")?;
+    let mut left = store.get_fuel()?;
+    let mut slice = left.min(FUEL_SLICE);
+    left -= slice;
+    store.set_fuel(slice)?;
+    let mut call = sort.call_resumable(&mut *store, ())?;
+    loop {
+        call = match call {
+            TypedResumableCall::Finished(()) => return Ok(()),
+            TypedResumableCall::HostTrap(trap) => {
+                return Err(wasmi::Error::new(trap.host_error().to_string()));
+            }
+            TypedResumableCall::OutOfFuel(_) if left == 0 => {
+                return Err(TrapCode::OutOfFuel.into());
+            }
+            TypedResumableCall::OutOfFuel(out) => {
+                store.data().int.checkpoint();
+                slice = left.min(FUEL_SLICE);
+                left -= slice;
+                store.set_fuel(slice)?;
+                out.resume(&mut *store)?
+            }
+        };
+    }
+}